@@ -1,37 +1,145 @@
 use eframe::{NativeOptions, egui};
 use egui::{Color32, Context, CornerRadius, Pos2, Rect, RichText};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::plugin::{Plugin, Plugins, Token};
+use crate::corrections::{self, Correction};
+use crate::history::{History, HistoryEntry};
+use crate::plugin::{
+    Example, Furigana, Kanji, LookupResult, Plugin, PluginDescriptor, PluginRegistry, Tag, Term,
+    Token, contains_kanji,
+};
+use crate::settings::{Settings, SettingsAction};
+use crate::theme::Theme;
+use crate::watch_filter::WatchFilter;
 
 //pub const WINDOW_INIT_WIDTH: i16 = 450;
 //pub const WINDOW_INIT_HEIGHT: i16 = 450;
 pub const APP_NAME: &str = "Popup Dictionary";
 
-const PRIMARY_BACKGROUND_COLOR: Color32 = Color32::from_rgb(30, 30, 30);
-pub const SECONDARY_BACKGROUND_COLOR: Color32 = Color32::from_rgb(50, 50, 50);
-pub const PRIMARY_TEXT_COLOR: Color32 = Color32::WHITE;
-pub const SECONDARY_TEXT_COLOR: Color32 = Color32::GRAY;
-pub const LIGHT_TEXT_COLOR: Color32 = Color32::LIGHT_GRAY;
-pub const BIG_TEXT_SIZE: f32 = 24.0;
-const PRIMARY_TEXT_SIZE: f32 = 20.0;
-pub const SMALL_TEXT_SIZE: f32 = 18.0;
-pub const TINY_TEXT_SIZE: f32 = 14.0;
-pub const SPACING_SIZE: f32 = 10.0;
-pub const CORNER_RADIUS: u8 = 4;
+const MAX_QUEUE_LENGTH: usize = 100;
+// How long typing in edit mode has to pause before the text is tokenized again
+const EDIT_PREVIEW_DELAY: Duration = Duration::from_millis(300);
+// Example sentences shown under a term before "Show more"
+const SHOWN_EXAMPLES: usize = 3;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
     pub initial_plugin: Option<String>,
     pub open_at_cursor: bool,
     pub wrapped: bool,
+    pub furigana: bool,
     pub initial_width: u16,
     pub initial_height: u16,
     pub show_tray_icon: bool,
     pub font: String,
+    pub text_scale: f32,
+    pub theme: crate::theme::ThemeConfig,
+    pub remember_corrections: bool,
+    pub queue: bool,
+    pub filters: crate::watch_filter::WatchFilters,
+    pub output: crate::output::OutputMode,
+}
+
+/// An input text to show and the config it was received with.
+pub type Popup = (String, Config);
+/// Following popups that an open window shows in place instead of a new window being opened.
+pub type PopupReceiver = Arc<Mutex<Receiver<Popup>>>;
+/// Watch filter compiled from the config shared with watch mode, replaced along with it.
+pub type SharedWatchFilter = Arc<Mutex<Arc<crate::watch_filter::WatchFilter>>>;
+/// Context of the open window, if there is one, to wake it up for new popups.
+pub type WindowContext = Arc<Mutex<Option<Context>>>;
+
+/// Sends popups to watch mode and wakes up the open window, so it doesn't have to poll for them.
+#[derive(Clone)]
+pub struct PopupSender {
+    sender: Sender<Popup>,
+    window: WindowContext,
+}
+
+impl PopupSender {
+    pub fn new(sender: Sender<Popup>, window: WindowContext) -> Self {
+        Self { sender, window }
+    }
+
+    pub fn send(&self, popup: Popup) -> Result<(), Box<dyn std::error::Error>> {
+        self.sender
+            .send(popup)
+            .map_err(|_| "Watch mode is no longer running.")?;
+        if let Some(ctx) = self.window.lock().unwrap().as_ref() {
+            ctx.request_repaint();
+        }
+        Ok(())
+    }
+}
+
+/// Texts collected with `--queue` and the one that is shown.
+#[derive(Default)]
+pub struct Queue {
+    entries: Vec<String>,
+    index: usize,
 }
 
-pub fn run_app(sentence: &str, config: Config) -> Result<(), eframe::Error> {
+impl Queue {
+    /// Adds a text, dropping the oldest ones past `MAX_QUEUE_LENGTH`.
+    fn push(&mut self, sentence: String) {
+        self.entries.push(sentence);
+        if self.entries.len() > MAX_QUEUE_LENGTH {
+            let excess: usize = self.entries.len() - MAX_QUEUE_LENGTH;
+            self.entries.drain(..excess);
+            self.index = self.index.saturating_sub(excess);
+        }
+    }
+}
+
+/// State a window shares with the running watch mode, or with the window's own instance
+/// listening for other invocations.
+#[derive(Clone)]
+pub struct Watching {
+    pub popups: PopupReceiver,
+    pub window: WindowContext,
+    // Kept for the next window, so texts not stepped to yet are still there in it
+    pub queue: Arc<Mutex<Queue>>,
+    // Settings saved in the window apply to the following lookups through these
+    pub config: Arc<Mutex<Config>>,
+    pub filter: SharedWatchFilter,
+    pub ocr_model: Arc<AtomicUsize>,
+    pub active_plugin: Arc<AtomicUsize>,
+}
+
+// Last text copied from the popup, so watch mode doesn't look it up again
+static OWN_COPY: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn is_own_copy(text: &str) -> bool {
+    OWN_COPY
+        .lock()
+        .is_ok_and(|own_copy| own_copy.as_deref() == Some(text))
+}
+
+fn copy_to_clipboard(text: String) {
+    if let Ok(mut own_copy) = OWN_COPY.lock() {
+        *own_copy = Some(text.clone());
+    }
+
+    std::thread::spawn(|| {
+        tracing::debug!("Trying to copy text to clipboard.");
+        let mut clipboard: arboard::Clipboard = arboard::Clipboard::new().unwrap();
+        clipboard.set_text(text).unwrap();
+        std::thread::sleep(std::time::Duration::from_secs(1));
+        drop(clipboard); // since clipboard is dropped here, linux users need a clipboard manager to retain data
+        tracing::debug!("Successfully copied text to clipboard.");
+    });
+}
+
+pub fn run_app(
+    sentence: &str,
+    config: Config,
+    watching: Option<Watching>,
+) -> Result<(), eframe::Error> {
     #[cfg(feature = "hyprland-support")]
     let is_hyprland: bool = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok();
     #[cfg(feature = "hyprland-support")]
@@ -108,7 +216,10 @@ pub fn run_app(sentence: &str, config: Config) -> Result<(), eframe::Error> {
             .unwrap_or(String::from("None")),
         config.wrapped
     );
-    eframe::run_native(
+    let window: Option<WindowContext> = watching
+        .as_ref()
+        .map(|watching| Arc::clone(&watching.window));
+    let result: Result<(), eframe::Error> = eframe::run_native(
         APP_NAME,
         options,
         Box::new(|cc| {
@@ -119,15 +230,40 @@ pub fn run_app(sentence: &str, config: Config) -> Result<(), eframe::Error> {
                 #[cfg(feature = "hyprland-support")]
                 is_hyprland,
                 sentence,
+                watching,
             )))
         }),
-    )
+    );
+    if let Some(window) = window {
+        *window.lock().unwrap() = None;
+    }
+    result
 }
 
 enum PluginState {
     Initial,
     Loading,
     Ready(Box<dyn Plugin>),
+    Failed(String),
+}
+
+enum TokenAction {
+    Select(usize),
+    Merge(usize),        // with the following token
+    Split(usize, usize), // in front of the given character
+    UndoCorrection,
+}
+
+/// A plugin loaded while typing in edit mode: its load generation, sentence and the plugin.
+type Preview = (usize, String, Box<dyn Plugin>);
+
+/// A word typed into the search field, looked up apart from the tokens of the sentence.
+#[derive(Default)]
+struct Search {
+    query: String,
+    result: Option<Result<LookupResult, String>>, // shown instead of the selected token's
+    pending: bool,                                // search once the plugin is ready
+    focus: bool,                                  // focus the field on the next frame
 }
 
 pub struct MyApp {
@@ -138,12 +274,25 @@ pub struct MyApp {
     sentence: String,
     selected_token_index: Option<usize>,
     plugin_state: Arc<Mutex<PluginState>>,
-    available_plugins: Vec<Plugins>,
+    load_generation: Arc<AtomicUsize>, // increased by every load, so older loads know they are stale
+    loaded_sentence: String,           // sentence the shown tokens are from
+    preview: Arc<Mutex<Option<Preview>>>,
+    edited_at: Option<Instant>, // last change in edit mode that wasn't tokenized yet
+    available_plugins: &'static [PluginDescriptor],
     active_plugin_index: usize,
+    theme: Theme,
     theme_is_set: bool,
     main_frame: Option<egui::containers::Frame>,
     edit_mode: bool,
     was_edited: bool,
+    lookup_cache: HashMap<usize, Result<LookupResult, String>>, // by token index
+    watching: Option<Watching>,
+    queue: Arc<Mutex<Queue>>,
+    settings: Option<Settings>,
+    scroll_delta: f32, // requested with the keyboard, applied to the definitions
+    restored_token_index: Option<usize>, // selected once the sentence from the history is loaded
+    search: Option<Search>, // open search field
+    corrections: Vec<Correction>, // applied to the tokens of every load, in order
 }
 
 impl MyApp {
@@ -153,34 +302,84 @@ impl MyApp {
         init_pos: Option<Pos2>,
         #[cfg(feature = "hyprland-support")] is_hyprland: bool,
         sentence: &str,
+        watching: Option<Watching>,
     ) -> Self {
         crate::font_helper::load_main_font(&cc.egui_ctx, &config.font);
 
-        let available_plugins: Vec<Plugins> = Plugins::all();
+        let registry: &'static PluginRegistry = PluginRegistry::global();
+        let available_plugins: &'static [PluginDescriptor] = registry.all();
 
         let init_plugin_idx: usize = if let Some(init_plugin) = &config.initial_plugin {
-            available_plugins
-                .iter()
-                .position(|p| p.name() == init_plugin)
-                .unwrap_or(0)
+            registry.position(init_plugin).unwrap_or_else(|| {
+                tracing::warn!("Unknown initial plugin {}, using default.", init_plugin);
+                0
+            })
         } else {
             0
         };
 
+        let theme: Theme = config.theme.resolve().unwrap_or_else(|e| {
+            tracing::warn!("Invalid theme, using default: {e}");
+            Theme::default()
+        });
+        let corrections: Vec<Correction> = if config.remember_corrections {
+            corrections::load().unwrap_or_else(|e| {
+                tracing::warn!("Could not load remembered corrections due to error: {e}");
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+
+        if let Some(watching) = &watching {
+            *watching.window.lock().unwrap() = Some(cc.egui_ctx.clone());
+        }
+
+        // A new window continues after the text the last one showed, which is the new text
+        // unless the last window was closed before stepping through the whole queue
+        let queue: Arc<Mutex<Queue>> = match &watching {
+            Some(watching) if config.queue => Arc::clone(&watching.queue),
+            _ => Arc::default(),
+        };
+        let sentence: String = {
+            let mut queue = queue.lock().unwrap();
+            queue.push(sentence.to_string());
+            if queue.entries.len() > 1 {
+                queue.index += 1;
+            }
+            if queue.index + 1 < queue.entries.len() {
+                tracing::info!("Continuing with the queued texts the last window didn't show.");
+            }
+            queue.entries[queue.index].to_owned()
+        };
+
         let mut app = Self {
             config,
             init_pos,
             #[cfg(feature = "hyprland-support")]
             is_hyprland,
-            sentence: sentence.to_string(),
+            sentence,
             selected_token_index: None,
             plugin_state: Arc::new(Mutex::new(PluginState::Initial)),
+            load_generation: Arc::new(AtomicUsize::new(0)),
+            loaded_sentence: String::new(),
+            preview: Arc::new(Mutex::new(None)),
+            edited_at: None,
             available_plugins,
             active_plugin_index: init_plugin_idx,
+            theme,
             theme_is_set: false,
             main_frame: None,
             edit_mode: false,
             was_edited: false,
+            lookup_cache: HashMap::new(),
+            watching,
+            queue,
+            settings: None,
+            scroll_delta: 0.0,
+            restored_token_index: None,
+            search: None,
+            corrections,
         };
 
         app.try_load_plugin(init_plugin_idx, false);
@@ -215,7 +414,7 @@ impl MyApp {
             "Trying to load plugin: {}",
             self.available_plugins.get(plugin_index).map_or(
                 format!("{}?", plugin_index),
-                |plugin| format!("{}.", plugin.name())
+                |plugin| format!("{}.", plugin.name)
             )
         );
 
@@ -223,125 +422,1184 @@ impl MyApp {
             return;
         }
 
-        let state_clone: Arc<Mutex<PluginState>> = Arc::clone(&self.plugin_state);
-        {
-            let mut state = state_clone.lock().unwrap();
-            match *state {
-                PluginState::Loading => {
-                    tracing::info!("A plugin is currently loading.");
-                    return;
-                }
-                PluginState::Ready(_) => {
-                    if self.active_plugin_index == plugin_index && !force {
-                        tracing::info!("The same plugin is already loaded.");
-                        return;
-                    }
-                    *state = PluginState::Loading;
-                }
-                _ => {
-                    *state = PluginState::Loading;
+        let state_clone: Arc<Mutex<PluginState>> = Arc::clone(&self.plugin_state);
+        let generation: usize = {
+            let mut state = state_clone.lock().unwrap();
+            let same_plugin: bool = self.active_plugin_index == plugin_index && !force;
+            match *state {
+                PluginState::Loading if same_plugin => {
+                    tracing::info!("The same plugin is already loading.");
+                    return;
+                }
+                PluginState::Ready(_) if same_plugin => {
+                    tracing::info!("The same plugin is already loaded.");
+                    return;
+                }
+                _ => {
+                    *state = PluginState::Loading;
+                }
+            }
+            // Increased while holding the lock, so a stale load can't sneak in after this one
+            self.load_generation.fetch_add(1, Ordering::Relaxed) + 1
+        };
+
+        if !self.sentence.trim().is_empty() {
+            History::global().lock().unwrap().push(&self.sentence);
+        }
+
+        let active_plugin: &'static PluginDescriptor = &self.available_plugins[plugin_index];
+        let plugin_sentence: String = self.sentence.to_owned();
+        let load_generation: Arc<AtomicUsize> = Arc::clone(&self.load_generation);
+        let corrections: Vec<Correction> = self.corrections.clone();
+        std::thread::spawn(move || {
+            let new_state: PluginState = match active_plugin.generate(&plugin_sentence) {
+                Ok(mut plugin) => {
+                    corrections::apply_to_plugin(&mut plugin, &corrections);
+                    PluginState::Ready(plugin)
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to load plugin {} due to error: {e}",
+                        active_plugin.name
+                    );
+                    PluginState::Failed(e.to_string())
+                }
+            };
+
+            let mut state = state_clone.lock().unwrap();
+            // A newer load started while this one was running, which can't be stopped
+            if load_generation.load(Ordering::Relaxed) != generation {
+                tracing::debug!("Discarding stale load of plugin {}.", active_plugin.name);
+                return;
+            }
+            *state = new_state;
+        });
+
+        self.selected_token_index = None;
+        self.active_plugin_index = plugin_index;
+        self.loaded_sentence = self.sentence.to_owned();
+        self.edited_at = None;
+        self.lookup_cache.clear();
+        self.scroll_delta = 0.0;
+        if let Some(search) = &mut self.search
+            && search.result.take().is_some()
+        {
+            // Search again with the new plugin
+            search.pending = true;
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        matches!(*self.plugin_state.lock().unwrap(), PluginState::Ready(_))
+    }
+
+    /// Tokenizes the text being edited in the background. Unlike `try_load_plugin`, the shown
+    /// tokens stay until the new ones are loaded, so the text box doesn't disappear while typing.
+    fn load_preview(&mut self, ctx: &Context) {
+        let generation: usize = {
+            let state = self.plugin_state.lock().unwrap();
+            if !matches!(*state, PluginState::Ready(_)) {
+                return;
+            }
+            self.load_generation.fetch_add(1, Ordering::Relaxed) + 1
+        };
+        tracing::debug!("Tokenizing edited input text.");
+
+        let active_plugin: &'static PluginDescriptor =
+            &self.available_plugins[self.active_plugin_index];
+        let plugin_sentence: String = self.sentence.to_owned();
+        let load_generation: Arc<AtomicUsize> = Arc::clone(&self.load_generation);
+        let preview: Arc<Mutex<Option<Preview>>> = Arc::clone(&self.preview);
+        let corrections: Vec<Correction> = self.corrections.clone();
+        let ctx: Context = ctx.clone();
+        std::thread::spawn(move || {
+            match active_plugin.generate(&plugin_sentence) {
+                Ok(mut plugin) => {
+                    corrections::apply_to_plugin(&mut plugin, &corrections);
+                    let mut preview = preview.lock().unwrap();
+                    if load_generation.load(Ordering::Relaxed) == generation {
+                        *preview = Some((generation, plugin_sentence, plugin));
+                        ctx.request_repaint();
+                    }
+                }
+                // Likely a half typed text, the tokens of the last one stay
+                Err(e) => tracing::debug!("Could not tokenize edited input text: {e}"),
+            }
+        });
+    }
+
+    fn receive_preview(&mut self) {
+        let Some((generation, sentence, plugin)) = self.preview.lock().unwrap().take() else {
+            return;
+        };
+        {
+            let mut state = self.plugin_state.lock().unwrap();
+            if generation != self.load_generation.load(Ordering::Relaxed)
+                || !matches!(*state, PluginState::Ready(_))
+            {
+                return;
+            }
+            *state = PluginState::Ready(plugin);
+        }
+
+        self.loaded_sentence = sentence;
+        self.selected_token_index = None;
+        self.lookup_cache.clear();
+        self.scroll_delta = 0.0;
+    }
+
+    fn receive_popups(&mut self, ctx: &Context) {
+        let Some(watching) = &self.watching else {
+            return;
+        };
+
+        let received: Vec<Popup> = watching.popups.lock().unwrap().try_iter().collect();
+        if self.config.queue {
+            // Queued texts are only shown once the user steps to them
+            let mut queue = self.queue.lock().unwrap();
+            for (sentence, _) in received {
+                tracing::info!("Adding new input text to the queue.");
+                queue.push(sentence);
+            }
+            return;
+        }
+
+        let Some((sentence, config)) = received.into_iter().last() else {
+            return;
+        };
+        tracing::info!("Showing new input text in the open window.");
+
+        // Watched content asks for the plugin selected in the tray, so only a different one was
+        // asked for explicitly, e.g. with --initial-plugin by a forwarded invocation. Otherwise
+        // the window keeps its current plugin. Other settings always stay as they are.
+        let requested_plugin: Option<usize> = config
+            .initial_plugin
+            .as_ref()
+            .and_then(|name| PluginRegistry::global().position(name))
+            .filter(|idx| {
+                *idx != watching.active_plugin.load(Ordering::Relaxed)
+                    && *idx != self.active_plugin_index
+            });
+
+        self.sentence = sentence;
+        self.edit_mode = false;
+        match requested_plugin {
+            Some(plugin_index) => self.try_load_plugin(plugin_index, true),
+            None => self.was_edited = true,
+        }
+
+        if self.config.open_at_cursor {
+            match crate::window_helper::get_optimal_init_pos(
+                #[cfg(feature = "hyprland-support")]
+                self.is_hyprland,
+                self.config.initial_width as f32,
+                self.config.initial_height as f32,
+            ) {
+                Ok(optimal_pos) => self.init_pos = Some(optimal_pos),
+                Err(e) => {
+                    tracing::warn!("Could not get optimal window position due to error: {e}")
+                }
+            }
+        }
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+    }
+
+    /// Shortcuts, so the popup can be used without the mouse.
+    fn handle_keyboard(&mut self, ctx: &Context) {
+        let pressed = |key: egui::Key| {
+            ctx.input(|input| {
+                input.key_pressed(key) && !input.modifiers.command && !input.modifiers.alt
+            })
+        };
+
+        if self.settings.is_some() {
+            if pressed(egui::Key::Escape) {
+                tracing::info!("Closing settings.");
+                self.settings = None;
+            }
+            return;
+        }
+        // Keys typed into a text field are not shortcuts
+        if self.edit_mode || ctx.wants_keyboard_input() {
+            return;
+        }
+
+        if pressed(egui::Key::Escape) {
+            if self.search.is_some() {
+                self.close_search();
+            } else {
+                tracing::info!("Closing the window.");
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            return;
+        }
+
+        if ctx.input(|input| input.modifiers.alt) {
+            if ctx.input(|input| input.key_pressed(egui::Key::ArrowLeft)) {
+                self.step_history(false);
+            }
+            if ctx.input(|input| input.key_pressed(egui::Key::ArrowRight)) {
+                self.step_history(true);
+            }
+            return;
+        }
+
+        if pressed(egui::Key::ArrowLeft) || pressed(egui::Key::H) {
+            self.select_next_token(false);
+        }
+        if pressed(egui::Key::ArrowRight) || pressed(egui::Key::L) {
+            self.select_next_token(true);
+        }
+
+        let mut plugin_to_load: Option<usize> = None;
+        if pressed(egui::Key::Tab) {
+            let plugin_count: usize = self.available_plugins.len();
+            plugin_to_load = Some(if ctx.input(|input| input.modifiers.shift) {
+                (self.active_plugin_index + plugin_count - 1) % plugin_count
+            } else {
+                (self.active_plugin_index + 1) % plugin_count
+            });
+            // Tab also moves the focus between buttons, which would then react to Enter
+            if let Some(focused) = ctx.memory(|memory| memory.focused()) {
+                ctx.memory_mut(|memory| memory.surrender_focus(focused));
+            }
+        }
+        const NUMBER_KEYS: [egui::Key; 9] = [
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+            egui::Key::Num5,
+            egui::Key::Num6,
+            egui::Key::Num7,
+            egui::Key::Num8,
+            egui::Key::Num9,
+        ];
+        for (idx, key) in NUMBER_KEYS.into_iter().enumerate() {
+            if idx < self.available_plugins.len() && pressed(key) {
+                plugin_to_load = Some(idx);
+            }
+        }
+        if let Some(idx) = plugin_to_load
+            && idx != self.active_plugin_index
+        {
+            self.try_load_plugin(idx, false);
+        }
+
+        if pressed(egui::Key::E) {
+            tracing::info!("Entering edit mode.");
+            self.edit_mode = true;
+        }
+        if pressed(egui::Key::C) {
+            copy_to_clipboard(self.sentence.to_owned());
+        }
+        if pressed(egui::Key::Slash) {
+            self.open_search();
+        }
+
+        let line: f32 = self.theme.small_text_size * 2.0;
+        let page: f32 = ctx.content_rect().height() * 0.8;
+        for (key, delta) in [
+            (egui::Key::ArrowUp, line),
+            (egui::Key::ArrowDown, -line),
+            (egui::Key::PageUp, page),
+            (egui::Key::PageDown, -page),
+        ] {
+            if pressed(key) {
+                self.scroll_delta += delta;
+            }
+        }
+    }
+
+    fn select_next_token(&mut self, forward: bool) {
+        let PluginState::Ready(plugin) = &*self.plugin_state.lock().unwrap() else {
+            return;
+        };
+        let Some(current) = self.selected_token_index else {
+            return;
+        };
+
+        let tokens: &Vec<Token> = plugin.get_tokens();
+        let next: Option<usize> = if forward {
+            (current + 1..tokens.len()).find(|&idx| tokens[idx].is_valid())
+        } else {
+            (0..current).rev().find(|&idx| tokens[idx].is_valid())
+        };
+        if next.is_some() {
+            self.selected_token_index = next;
+            if let Some(search) = &mut self.search {
+                search.result = None;
+            }
+        }
+    }
+
+    /// Splits or merges tokens at `token_index` of the shown plugin and keeps the correction
+    /// for the following loads.
+    fn correct_tokens(
+        &mut self,
+        token_index: usize,
+        correction: impl FnOnce(&[Token], usize) -> Option<Correction>,
+    ) {
+        let correction: Correction = {
+            let mut state = self.plugin_state.lock().unwrap();
+            let PluginState::Ready(plugin) = &mut *state else {
+                return;
+            };
+            let Some(correction) = correction(plugin.get_tokens(), token_index) else {
+                return;
+            };
+            corrections::apply_at(plugin, token_index, &correction);
+            correction
+        };
+        tracing::info!(
+            "Correcting words {:?} to {:?}.",
+            correction.from,
+            correction.to
+        );
+
+        if self.config.remember_corrections
+            && let Err(e) = corrections::remember(&correction)
+        {
+            tracing::warn!("Could not remember correction due to error: {e}");
+        }
+        self.corrections.push(correction);
+        self.selected_token_index = Some(token_index);
+        self.lookup_cache.clear();
+        self.scroll_delta = 0.0;
+        if let Some(search) = &mut self.search {
+            search.result = None;
+        }
+    }
+
+    fn undo_correction(&mut self) {
+        let Some(correction) = self.corrections.pop() else {
+            return;
+        };
+        tracing::info!(
+            "Undoing correction of words {:?} to {:?}.",
+            correction.from,
+            correction.to
+        );
+
+        if self.config.remember_corrections
+            && let Err(e) = corrections::forget(&correction)
+        {
+            tracing::warn!("Could not forget correction due to error: {e}");
+        }
+        // The original tokens are only known to the plugin, so it is loaded again
+        self.try_load_plugin(self.active_plugin_index, true);
+    }
+
+    fn open_search(&mut self) {
+        tracing::info!("Opening search.");
+        let search: &mut Search = self.search.get_or_insert_with(Search::default);
+        search.focus = true;
+    }
+
+    fn close_search(&mut self) {
+        tracing::info!("Closing search.");
+        self.search = None;
+    }
+
+    fn step_history(&mut self, forward: bool) {
+        let entry: Option<HistoryEntry> = {
+            let mut history = History::global().lock().unwrap();
+            if forward {
+                history.forward()
+            } else {
+                history.back()
+            }
+        };
+        let Some(entry) = entry else {
+            return;
+        };
+        tracing::info!(
+            "Going {} in the history.",
+            if forward { "forward" } else { "back" }
+        );
+
+        self.sentence = entry.sentence;
+        self.restored_token_index = entry.selected_token_index;
+        self.edit_mode = false;
+        self.was_edited = true;
+    }
+
+    fn show_queued(&mut self, queue_index: usize) {
+        let mut queue = self.queue.lock().unwrap();
+        let Some(sentence) = queue.entries.get(queue_index) else {
+            return;
+        };
+        tracing::info!("Showing queued input text {}.", queue_index + 1);
+
+        self.sentence = sentence.to_owned();
+        queue.index = queue_index;
+        self.edit_mode = false;
+        self.was_edited = true;
+    }
+
+    fn open_settings(&mut self) {
+        let plugin_index: usize = match &self.watching {
+            // In watch mode, the plugin selected in the tray menu is the one new popups use
+            Some(watching) => watching.active_plugin.load(Ordering::Relaxed),
+            None => self
+                .config
+                .initial_plugin
+                .as_ref()
+                .and_then(|name| PluginRegistry::global().position(name))
+                .unwrap_or(0),
+        };
+        let ocr_model: usize = match &self.watching {
+            Some(watching) => watching.ocr_model.load(Ordering::Relaxed),
+            None => crate::config_file::load().map_or(0, |file_config| file_config.ocr.model()),
+        };
+
+        tracing::info!("Opening settings.");
+        self.settings = Some(Settings::new(&self.config, plugin_index, ocr_model));
+    }
+
+    fn save_settings(&mut self, ctx: &Context) {
+        let Some(settings) = &mut self.settings else {
+            return;
+        };
+
+        let config: Config = match settings
+            .to_config(&self.config)
+            .and_then(|config| settings.save(&config).map(|_| config))
+        {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("Could not save settings due to error: {e}");
+                settings.set_error(e.to_string());
+                return;
+            }
+        };
+
+        if config.font != self.config.font {
+            // Looking for the font can take a while, e.g. if it has to be downloaded
+            let ctx: Context = ctx.clone();
+            let font: String = config.font.to_owned();
+            std::thread::spawn(move || {
+                crate::font_helper::load_main_font(&ctx, &font);
+                ctx.request_repaint();
+            });
+        }
+        match config.theme.resolve() {
+            Ok(theme) => self.theme = theme,
+            Err(e) => tracing::warn!("Invalid theme, keeping the current one: {e}"),
+        }
+
+        if let Some(watching) = &self.watching {
+            watching
+                .active_plugin
+                .store(settings.plugin_index(), Ordering::Relaxed);
+            watching
+                .ocr_model
+                .store(settings.ocr_model(), Ordering::Relaxed);
+            match WatchFilter::new(config.filters.clone()) {
+                Ok(filter) => *watching.filter.lock().unwrap() = Arc::new(filter),
+                Err(e) => tracing::warn!("Keeping the previous watch filters due to error: {e}"),
+            }
+            *watching.config.lock().unwrap() = config.clone();
+        }
+        self.config = config;
+        self.settings = None;
+        self.set_theme(ctx);
+    }
+
+    fn display_settings(&mut self, ctx: &Context) {
+        let Some(settings) = &mut self.settings else {
+            return;
+        };
+
+        let theme: Theme = self.theme;
+        let mut open: bool = true;
+        let mut action: Option<SettingsAction> = None;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .vscroll(true)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .frame(egui::Frame::window(&ctx.style()).fill(theme.secondary_background_color))
+            .show(ctx, |ui| {
+                action = settings.display(ui, &theme);
+            });
+
+        if !open {
+            action = Some(SettingsAction::Close);
+        }
+        match action {
+            Some(SettingsAction::Save) => self.save_settings(ctx),
+            Some(SettingsAction::Close) => {
+                tracing::info!("Closing settings.");
+                self.settings = None;
+            }
+            None => {}
+        }
+    }
+
+    fn set_theme(&mut self, ctx: &Context) {
+        let theme: Theme = self.theme;
+        let mut visuals = theme.visuals();
+        visuals.interact_cursor = Some(egui::CursorIcon::PointingHand);
+        visuals.window_corner_radius = CornerRadius::ZERO;
+        visuals.window_shadow = egui::Shadow::NONE;
+        visuals.window_stroke = egui::Stroke::NONE;
+        visuals.indent_has_left_vline = false;
+        ctx.set_visuals(visuals);
+
+        let mut spacing = ctx.style().spacing.clone();
+        spacing.button_padding = egui::vec2(2.0, 2.0);
+        spacing.icon_spacing = 2.0;
+        spacing.item_spacing = egui::vec2(4.0, 4.0);
+        spacing.indent = 4.0;
+        spacing.menu_spacing = 2.0;
+        spacing.window_margin = egui::Margin {
+            left: 2,
+            right: 2,
+            top: 2,
+            bottom: 2,
+        };
+        ctx.style_mut(|style| style.spacing = spacing);
+
+        let mut style = (*ctx.style()).clone();
+        style.text_styles = [
+            (
+                egui::TextStyle::Heading,
+                egui::FontId::new(theme.big_text_size, egui::FontFamily::Proportional),
+            ),
+            (
+                egui::TextStyle::Body,
+                egui::FontId::new(theme.primary_text_size, egui::FontFamily::Proportional),
+            ),
+            (
+                egui::TextStyle::Button,
+                egui::FontId::new(theme.primary_text_size, egui::FontFamily::Proportional),
+            ),
+            (
+                egui::TextStyle::Small,
+                egui::FontId::new(theme.small_text_size, egui::FontFamily::Proportional),
+            ),
+        ]
+        .into();
+        ctx.set_style(style);
+        ctx.set_zoom_factor(self.config.text_scale);
+
+        self.main_frame = Some(egui::containers::Frame {
+            corner_radius: CornerRadius::ZERO,
+            fill: theme.primary_background_color,
+            inner_margin: egui::Margin {
+                left: 2,
+                right: 2,
+                top: 2,
+                bottom: 2,
+            },
+            outer_margin: egui::Margin {
+                left: 2,
+                right: 2,
+                top: 2,
+                bottom: 2,
+            },
+            shadow: egui::Shadow::NONE,
+            stroke: egui::Stroke::NONE,
+        });
+    }
+
+    fn lookup_token(plugin: &dyn Plugin, token: &Token) -> Result<LookupResult, String> {
+        plugin.lookup(token).map_err(|e| {
+            tracing::debug!(
+                "Could not look up token {} due to error: {e}",
+                token.input_word
+            );
+            e.to_string()
+        })
+    }
+
+    /// Readings shown above the tokens with kanji in the header, taken from the plugin if it
+    /// knows them from tokenizing, otherwise from their lookups. Only offline plugins look up
+    /// every token for it, so there isn't a request for every token of the others.
+    fn token_readings(
+        lookup_cache: &mut HashMap<usize, Result<LookupResult, String>>,
+        plugin: &dyn Plugin,
+        tokens: &[Token],
+        look_up_all: bool,
+    ) -> Vec<Option<Vec<Furigana>>> {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(idx, token)| {
+                if !token.is_valid() || !contains_kanji(&token.input_word) {
+                    return None;
+                }
+                if let Some(furigana) = plugin.token_reading(token) {
+                    return Some(furigana);
+                }
+                let lookup_result: Option<&Result<LookupResult, String>> = if look_up_all {
+                    Some(
+                        lookup_cache
+                            .entry(idx)
+                            .or_insert_with(|| Self::lookup_token(plugin, token)),
+                    )
+                } else {
+                    lookup_cache.get(&idx)
+                };
+
+                lookup_result?
+                    .as_ref()
+                    .ok()?
+                    .terms
+                    .iter()
+                    .find_map(|term| term.furigana_for(&token.input_word))
+            })
+            .collect()
+    }
+
+    fn display_forms(ui: &mut egui::Ui, theme: &Theme, lookup_result: Option<&LookupResult>) {
+        match lookup_result {
+            Some(lookup_result) if !lookup_result.forms.is_empty() => {
+                ui.label(
+                    RichText::new(format!("Forms: {}", lookup_result.forms.join(", ")))
+                        .size(theme.tiny_text_size),
+                );
+            }
+            _ => {
+                ui.add_space(theme.tiny_text_size + theme.spacing_size + 1.0);
+            }
+        }
+        ui.scope(|ui| {
+            ui.style_mut()
+                .visuals
+                .widgets
+                .noninteractive
+                .bg_stroke
+                .color = theme.separator_color(40);
+            ui.separator();
+        });
+    }
+
+    fn display_terms(ui: &mut egui::Ui, theme: &Theme, terms: &[Term]) {
+        for term in terms {
+            ui.horizontal(|ui| {
+                if let Some(furigana_vec) = &term.furigana {
+                    Self::display_furigana(ui, theme, furigana_vec);
+                } else if !term.term.is_empty() {
+                    let furigana: Vec<Furigana> = vec![Furigana {
+                        ruby: term.term.to_string(),
+                        rt: Some(term.reading.to_string()),
+                    }];
+                    Self::display_furigana(ui, theme, &furigana);
+                } else {
+                    ui.label(RichText::new(&term.reading).heading());
+                }
+                for position in &term.pitch_accents {
+                    ui.add_space(theme.spacing_size * 0.5);
+                    Self::display_pitch_accent(ui, theme, &term.reading, *position);
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.add_space(theme.spacing_size);
+
+                    if ui
+                        .add(egui::Button::new(
+                            RichText::new("\u{1f4cb}").size(theme.tiny_text_size),
+                        ))
+                        .on_hover_text(
+                            RichText::new("Copy term to clipboard").size(theme.tiny_text_size),
+                        )
+                        .clicked()
+                    {
+                        // Copy button
+                        copy_to_clipboard(term.written_form().to_owned());
+                    }
+                });
+            });
+
+            let mut count: u32 = 0;
+            let mut last_tags: Option<&Vec<Tag>> = None;
+            for sense in &term.senses {
+                if last_tags != Some(&sense.tags) {
+                    last_tags = Some(&sense.tags);
+                    if count > 0 {
+                        ui.add_space(theme.spacing_size);
+                        count = 1;
+                    }
+                    Self::display_tags(ui, theme, &sense.tags);
+                }
+                if count == 0 {
+                    count = 1;
+                }
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        RichText::new(format!("{}.", count))
+                            .small()
+                            .color(theme.secondary_text_color),
+                    );
+                    ui.label(RichText::new(sense.glosses.join(", ")).small());
+                });
+                if !sense.info.is_empty() {
+                    ui.horizontal_top(|ui| {
+                        ui.label(
+                            RichText::new(format!("{}.", count))
+                                .small()
+                                .color(Color32::TRANSPARENT),
+                        );
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(
+                                RichText::new(sense.info.join("; "))
+                                    .size(theme.tiny_text_size * 0.9)
+                                    .color(theme.secondary_text_color),
+                            );
+                        });
+                    });
+                }
+
+                count += 1;
+            }
+            Self::display_examples(ui, theme, term);
+
+            ui.add_space(theme.spacing_size * 0.5);
+
+            let percent: f32 = 0.8;
+            let width: f32 = ui.available_width() * percent;
+            let margin: f32 = (ui.available_width() - width) / 2.0;
+
+            ui.horizontal(|ui| {
+                ui.add_space(margin);
+                let rect: egui::Rect = ui.allocate_space(egui::vec2(width, 1.0)).1;
+                ui.painter().line_segment(
+                    [rect.left_center(), rect.right_center()],
+                    egui::Stroke::new(1.0, theme.separator_color(80)),
+                );
+            });
+
+            ui.add_space(theme.spacing_size * 0.5);
+        }
+    }
+
+    fn display_examples(ui: &mut egui::Ui, theme: &Theme, term: &Term) {
+        if term.examples.is_empty() {
+            return;
+        }
+
+        // Kept per term while the window is open, also when selecting another word
+        let id: egui::Id = egui::Id::new(("examples", term.written_form(), &term.reading));
+        let show_all: bool = ui.data(|data| data.get_temp(id)).unwrap_or(false);
+        let shown: usize = if show_all {
+            term.examples.len()
+        } else {
+            term.examples.len().min(SHOWN_EXAMPLES)
+        };
+
+        ui.add_space(theme.spacing_size * 0.5);
+        for example in &term.examples[..shown] {
+            ui.add(egui::Label::new(Self::highlight_example(theme, example)).wrap());
+            ui.add(
+                egui::Label::new(
+                    RichText::new(&example.english)
+                        .size(theme.tiny_text_size)
+                        .color(theme.secondary_text_color),
+                )
+                .wrap(),
+            );
+            ui.add_space(theme.spacing_size * 0.3);
+        }
+
+        if term.examples.len() > SHOWN_EXAMPLES {
+            let text: String = if show_all {
+                String::from("Show fewer")
+            } else {
+                format!("Show more ({})", term.examples.len() - SHOWN_EXAMPLES)
+            };
+            if ui
+                .add(
+                    egui::Button::new(
+                        RichText::new(text)
+                            .size(theme.tiny_text_size)
+                            .color(theme.secondary_text_color),
+                    )
+                    .frame(false),
+                )
+                .clicked()
+            {
+                ui.data_mut(|data| data.insert_temp(id, !show_all));
+            }
+        }
+    }
+
+    /// The Japanese sentence with the term as written in it underlined.
+    fn highlight_example(theme: &Theme, example: &Example) -> egui::text::LayoutJob {
+        let format: egui::TextFormat = egui::TextFormat {
+            font_id: egui::FontId::proportional(theme.small_text_size),
+            color: theme.light_text_color,
+            ..Default::default()
+        };
+        let highlighted: egui::TextFormat = egui::TextFormat {
+            color: theme.primary_text_color,
+            underline: egui::Stroke::new(1.0, theme.primary_text_color),
+            ..format.clone()
+        };
+
+        let sentence: &str = &example.japanese;
+        let mut job: egui::text::LayoutJob = egui::text::LayoutJob::default();
+        match sentence
+            .find(&example.word)
+            .filter(|_| !example.word.is_empty())
+        {
+            Some(start) => {
+                let end: usize = start + example.word.len();
+                job.append(&sentence[..start], 0.0, format.clone());
+                job.append(&sentence[start..end], 0.0, highlighted);
+                job.append(&sentence[end..], 0.0, format);
+            }
+            None => job.append(sentence, 0.0, format),
+        }
+
+        job
+    }
+
+    fn display_kanji(ui: &mut egui::Ui, theme: &Theme, kanji: &[Kanji]) {
+        if kanji.is_empty() {
+            return;
+        }
+
+        ui.label(
+            RichText::new("Kanji")
+                .size(theme.tiny_text_size)
+                .color(theme.secondary_text_color),
+        );
+        for kanji in kanji {
+            ui.horizontal_top(|ui| {
+                ui.label(RichText::new(&kanji.character).size(theme.big_text_size * 1.5));
+                ui.vertical(|ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(RichText::new(kanji.meanings.join(", ")).small());
+                    });
+                    for (name, readings) in [("On", &kanji.onyomi), ("Kun", &kanji.kunyomi)] {
+                        if readings.is_empty() {
+                            continue;
+                        }
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(
+                                RichText::new(format!("{name}:"))
+                                    .size(theme.tiny_text_size)
+                                    .color(theme.secondary_text_color),
+                            );
+                            ui.label(RichText::new(readings.join("、")).size(theme.tiny_text_size));
+                        });
+                    }
+
+                    let mut details: Vec<String> = Vec::new();
+                    if let Some(stroke_count) = kanji.stroke_count {
+                        details.push(format!("{stroke_count} strokes"));
+                    }
+                    match kanji.grade {
+                        Some(grade @ 1..=6) => details.push(format!("Grade {grade}")),
+                        Some(8) => details.push(String::from("Jōyō")),
+                        Some(9..=10) => details.push(String::from("Jinmeiyō")),
+                        _ => {}
+                    }
+                    if let Some(jlpt) = kanji.jlpt {
+                        details.push(format!("Old JLPT {jlpt}"));
+                    }
+                    if let Some(frequency) = kanji.frequency {
+                        details.push(format!("Frequency #{frequency}"));
+                    }
+                    if !details.is_empty() {
+                        ui.label(
+                            RichText::new(details.join(" · "))
+                                .size(theme.tiny_text_size * 0.9)
+                                .color(theme.secondary_text_color),
+                        );
+                    }
+                });
+            });
+            ui.add_space(theme.spacing_size * 0.5);
+        }
+    }
+
+    fn display_tags(ui: &mut egui::Ui, theme: &Theme, tags: &[Tag]) {
+        ui.horizontal_wrapped(|ui| {
+            for tag in tags {
+                Self::display_tag(ui, theme, tag);
+            }
+        });
+    }
+
+    fn display_tag(ui: &mut egui::Ui, theme: &Theme, tag: &Tag) {
+        let text_galley = ui.fonts_mut(|f| {
+            f.layout_no_wrap(
+                tag.name.to_string(),
+                egui::FontId::proportional(theme.tiny_text_size),
+                theme.primary_text_color,
+            )
+        });
+
+        let padding = egui::Vec2::new(4.0, 0.0);
+        let rect = egui::Rect::from_min_size(ui.cursor().min, text_galley.size() + (2.0 * padding));
+        let response = ui
+            .allocate_rect(rect, egui::Sense::hover())
+            .on_hover_text(RichText::new(&tag.description).size(theme.tiny_text_size));
+
+        if response.hovered() {
+            ui.ctx().set_cursor_icon(egui::CursorIcon::Help);
+        }
+
+        ui.painter().rect_filled(
+            rect,
+            egui::CornerRadius::same(theme.corner_radius),
+            theme.secondary_background_color,
+        );
+
+        ui.painter().galley(
+            (rect.center() - text_galley.size() / 2.0) - egui::Vec2::new(0.0, 2.0),
+            text_galley,
+            theme.primary_text_color,
+        );
+    }
+
+    /// Draws the reading with a line over its high morae, which drops after the accented one.
+    fn display_pitch_accent(ui: &mut egui::Ui, theme: &Theme, reading: &str, position: u32) {
+        let line_gap: f32 = 3.0;
+        let stroke: egui::Stroke = egui::Stroke::new(1.0, theme.light_text_color);
+
+        let morae: Vec<String> = Self::morae(reading);
+        let galleys: Vec<Arc<egui::Galley>> = morae
+            .iter()
+            .map(|mora| {
+                ui.fonts_mut(|f| {
+                    f.layout_no_wrap(
+                        mora.to_string(),
+                        egui::FontId::proportional(theme.tiny_text_size),
+                        theme.light_text_color,
+                    )
+                })
+            })
+            .collect();
+        let width: f32 = galleys.iter().map(|galley| galley.size().x).sum();
+        let text_height: f32 = galleys
+            .iter()
+            .map(|galley| galley.size().y)
+            .fold(0.0, f32::max);
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(width, text_height + line_gap),
+            egui::Sense::hover(),
+        );
+        let position: usize = position as usize;
+        let mut x: f32 = rect.left();
+        for (idx, galley) in galleys.into_iter().enumerate() {
+            let mora_width: f32 = galley.size().x;
+            let is_high: bool = match position {
+                0 => idx > 0,
+                1 => idx == 0,
+                _ => (1..position).contains(&idx),
+            };
+            if is_high {
+                ui.painter().line_segment(
+                    [
+                        egui::pos2(x, rect.top()),
+                        egui::pos2(x + mora_width, rect.top()),
+                    ],
+                    stroke,
+                );
+            }
+            if idx + 1 == position {
+                ui.painter().line_segment(
+                    [
+                        egui::pos2(x + mora_width, rect.top()),
+                        egui::pos2(x + mora_width, rect.top() + line_gap + text_height * 0.4),
+                    ],
+                    stroke,
+                );
+            }
+            ui.painter().galley(
+                egui::pos2(x, rect.top() + line_gap),
+                galley,
+                theme.light_text_color,
+            );
+            x += mora_width;
+        }
+
+        let pattern: &str = match position {
+            0 => "Heiban",
+            1 => "Atamadaka",
+            _ if position == morae.len() => "Odaka",
+            _ => "Nakadaka",
+        };
+        response.on_hover_text(
+            RichText::new(format!("Pitch accent: {pattern} [{position}]"))
+                .size(theme.tiny_text_size),
+        );
+    }
+
+    /// Splits a reading into morae, small kana belong to the kana before them.
+    fn morae(reading: &str) -> Vec<String> {
+        let mut morae: Vec<String> = Vec::new();
+        for c in reading.chars() {
+            match morae.last_mut() {
+                Some(mora) if "ゃゅょぁぃぅぇぉゎャュョァィゥェォヮ".contains(c) => {
+                    mora.push(c)
                 }
+                _ => morae.push(c.to_string()),
             }
         }
+        morae
+    }
 
-        let active_plugin: Plugins = self.available_plugins[plugin_index];
-        let plugin_sentence: String = self.sentence.to_owned();
-        std::thread::spawn(move || {
-            // TODO: Implement error handling, logging?
-            let plugin: Box<dyn Plugin> = active_plugin.generate(&plugin_sentence);
-            *state_clone.lock().unwrap() = PluginState::Ready(plugin);
-        });
+    fn display_furigana(ui: &mut egui::Ui, theme: &Theme, furigana_vec: &[Furigana]) {
+        let vertical_gap: f32 = 1.0;
 
-        self.selected_token_index = None;
-        self.active_plugin_index = plugin_index;
-    }
+        // calculate how wide (and tall) the entire string will be
+        let mut total_width: f32 = 0.0;
+        let mut max_height: f32 = 0.0;
+        let mut galley_data = Vec::new();
 
-    fn set_theme(&mut self, ctx: &Context) {
-        let mut visuals = egui::Visuals::dark();
-        visuals.override_text_color = Some(PRIMARY_TEXT_COLOR);
-        visuals.window_fill = PRIMARY_BACKGROUND_COLOR;
-        visuals.interact_cursor = Some(egui::CursorIcon::PointingHand);
-        visuals.window_corner_radius = CornerRadius::ZERO;
-        visuals.window_shadow = egui::Shadow::NONE;
-        visuals.window_stroke = egui::Stroke::NONE;
-        visuals.indent_has_left_vline = false;
-        ctx.set_visuals(visuals);
+        for furigana in furigana_vec {
+            let main_galley = ui.fonts_mut(|f| {
+                f.layout_no_wrap(
+                    furigana.ruby.to_string(),
+                    egui::FontId::proportional(theme.big_text_size),
+                    theme.primary_text_color,
+                )
+            });
 
-        let mut spacing = ctx.style().spacing.clone();
-        spacing.button_padding = egui::vec2(2.0, 2.0);
-        spacing.icon_spacing = 2.0;
-        spacing.item_spacing = egui::vec2(4.0, 4.0);
-        spacing.indent = 4.0;
-        spacing.menu_spacing = 2.0;
-        spacing.window_margin = egui::Margin {
-            left: 2,
-            right: 2,
-            top: 2,
-            bottom: 2,
-        };
-        ctx.style_mut(|style| style.spacing = spacing);
+            let furigana_galley = if let Some(reading) = &furigana.rt {
+                ui.fonts_mut(|f| {
+                    f.layout_no_wrap(
+                        reading.to_string(),
+                        egui::FontId::proportional(theme.tiny_text_size),
+                        theme.light_text_color,
+                    )
+                })
+            } else {
+                ui.fonts_mut(|f| {
+                    f.layout_no_wrap(
+                        "あ".to_string(), // invisible placeholder
+                        egui::FontId::proportional(theme.tiny_text_size),
+                        Color32::TRANSPARENT,
+                    )
+                })
+            };
 
-        let mut style = (*ctx.style()).clone();
-        style.text_styles = [
-            (
-                egui::TextStyle::Heading,
-                egui::FontId::new(BIG_TEXT_SIZE, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Body,
-                egui::FontId::new(PRIMARY_TEXT_SIZE, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Button,
-                egui::FontId::new(PRIMARY_TEXT_SIZE, egui::FontFamily::Proportional),
-            ),
-            (
-                egui::TextStyle::Small,
-                egui::FontId::new(SMALL_TEXT_SIZE, egui::FontFamily::Proportional),
-            ),
-        ]
-        .into();
-        ctx.set_style(style);
+            let char_width: f32 = main_galley.size().x.max(furigana_galley.size().x);
+            let char_height: f32 = main_galley.size().y + furigana_galley.size().y + vertical_gap;
 
-        self.main_frame = Some(egui::containers::Frame {
-            corner_radius: CornerRadius::ZERO,
-            fill: PRIMARY_BACKGROUND_COLOR,
-            inner_margin: egui::Margin {
-                left: 2,
-                right: 2,
-                top: 2,
-                bottom: 2,
-            },
-            outer_margin: egui::Margin {
-                left: 2,
-                right: 2,
-                top: 2,
-                bottom: 2,
-            },
-            shadow: egui::Shadow::NONE,
-            stroke: egui::Stroke::NONE,
+            total_width += char_width;
+            max_height = max_height.max(char_height);
+
+            galley_data.push((main_galley, furigana_galley, char_width));
+        }
+
+        // then draw without gap between galleys
+        let (rect, _) = ui.allocate_exact_size(
+            egui::Vec2::new(total_width, max_height),
+            egui::Sense::empty(),
+        );
+
+        let mut current_x: f32 = rect.left();
+        for (main_galley, furigana_galley, char_width) in galley_data {
+            let furigana_pos = egui::Pos2::new(
+                current_x + (char_width - furigana_galley.size().x) * 0.5,
+                rect.top(),
+            );
+            ui.painter()
+                .galley(furigana_pos, furigana_galley, Color32::PLACEHOLDER);
+
+            let main_pos = egui::Pos2::new(
+                current_x + (char_width - main_galley.size().x) * 0.5,
+                rect.top() + theme.tiny_text_size + vertical_gap,
+            );
+            ui.painter()
+                .galley(main_pos, main_galley, Color32::PLACEHOLDER);
+
+            current_x += char_width;
+        }
+    }
+
+    fn display_queue(&self, ui: &mut egui::Ui) -> Option<usize> {
+        let theme: &Theme = &self.theme;
+        let queue = self.queue.lock().unwrap();
+        let mut clicked_index: Option<usize> = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    queue.index > 0,
+                    egui::Button::new(RichText::new("\u{25C0}").size(theme.tiny_text_size)),
+                )
+                .on_hover_text(RichText::new("Previous text").size(theme.tiny_text_size))
+                .clicked()
+            {
+                clicked_index = Some(queue.index - 1);
+            }
+
+            egui::ComboBox::from_id_salt("queue_history")
+                .selected_text(
+                    RichText::new(format!("{}/{}", queue.index + 1, queue.entries.len()))
+                        .size(theme.tiny_text_size),
+                )
+                .show_ui(ui, |ui| {
+                    for (idx, sentence) in queue.entries.iter().enumerate() {
+                        let mut preview: String = sentence.chars().take(20).collect();
+                        if preview.len() < sentence.len() {
+                            preview.push('…');
+                        }
+                        if ui
+                            .selectable_label(
+                                idx == queue.index,
+                                RichText::new(preview).size(theme.tiny_text_size),
+                            )
+                            .clicked()
+                        {
+                            clicked_index = Some(idx);
+                        }
+                    }
+                });
+
+            if ui
+                .add_enabled(
+                    queue.index + 1 < queue.entries.len(),
+                    egui::Button::new(RichText::new("\u{25B6}").size(theme.tiny_text_size)),
+                )
+                .on_hover_text(RichText::new("Next text").size(theme.tiny_text_size))
+                .clicked()
+            {
+                clicked_index = Some(queue.index + 1);
+            }
         });
+
+        clicked_index
     }
 
     fn display_token_header(
         ui: &mut egui::Ui,
+        theme: &Theme,
         tokens: &Vec<Token>,
+        readings: &[Option<Vec<Furigana>>], // by token index, empty without furigana
         selected_token_idx: usize,
-    ) -> Option<usize> {
-        let mut clicked_index = None;
+        can_undo_correction: bool,
+    ) -> Option<TokenAction> {
+        let mut action: Option<TokenAction> = None;
         for (idx, token) in tokens.iter().enumerate() {
-            let mut label_text: RichText = RichText::new(&token.input_word).size(PRIMARY_TEXT_SIZE);
-            if token.is_valid() {
+            let mut label_text: RichText =
+                RichText::new(&token.input_word).size(theme.primary_text_size);
+            let secondary: bool = token.is_valid() && idx != selected_token_idx;
+
+            let response: egui::Response = if !readings.is_empty() {
+                Self::display_ruby_token(ui, theme, token, readings[idx].as_deref(), secondary)
+            } else if token.is_valid() {
                 label_text = label_text.underline();
-                if idx != selected_token_idx {
-                    label_text = label_text.color(SECONDARY_TEXT_COLOR);
+                if secondary {
+                    label_text = label_text.color(theme.secondary_text_color);
                 }
 
                 let text_size: egui::Vec2 = {
                     let temp_galley: Arc<egui::Galley> = ui.fonts_mut(|f| {
                         f.layout_no_wrap(
                             label_text.text().to_string(),
-                            egui::FontId::proportional(PRIMARY_TEXT_SIZE),
+                            egui::FontId::proportional(theme.primary_text_size),
                             Color32::PLACEHOLDER,
                         )
                     });
@@ -350,32 +1608,168 @@ impl MyApp {
                 let (background_rect, _) = ui.allocate_exact_size(text_size, egui::Sense::hover());
                 let label_rect: Rect = Rect::from_center_size(background_rect.center(), text_size);
 
-                let response = ui
-                    .scope_builder(egui::UiBuilder::new().max_rect(label_rect), |ui| {
-                        ui.label(label_text)
-                    })
-                    .inner;
+                ui.scope_builder(egui::UiBuilder::new().max_rect(label_rect), |ui| {
+                    ui.label(label_text)
+                })
+                .inner
+            } else {
+                ui.label(label_text)
+            };
+
+            if token.is_valid() {
                 if response.hovered() {
                     ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
                     ui.painter().rect_filled(
-                        background_rect,
-                        CornerRadius::same(CORNER_RADIUS),
+                        response.rect,
+                        CornerRadius::same(theme.corner_radius),
                         Color32::from_rgba_premultiplied(
-                            SECONDARY_BACKGROUND_COLOR.r(),
-                            SECONDARY_BACKGROUND_COLOR.g(),
-                            SECONDARY_BACKGROUND_COLOR.b(),
+                            theme.secondary_background_color.r(),
+                            theme.secondary_background_color.g(),
+                            theme.secondary_background_color.b(),
                             40,
                         ),
                     );
                 }
                 if response.clicked() {
-                    clicked_index = Some(idx);
+                    action = Some(TokenAction::Select(idx));
                 }
-            } else {
-                ui.label(label_text);
             }
+            response.context_menu(|ui| {
+                Self::display_token_menu(ui, tokens, idx, can_undo_correction, &mut action);
+            });
+        }
+        action
+    }
+
+    /// Draws a token of the header with its reading above the kanji. Tokens without a reading
+    /// leave the space above them empty, so all tokens line up.
+    fn display_ruby_token(
+        ui: &mut egui::Ui,
+        theme: &Theme,
+        token: &Token,
+        reading: Option<&[Furigana]>,
+        secondary: bool,
+    ) -> egui::Response {
+        let vertical_gap: f32 = 1.0;
+        let text_color: Color32 = if secondary {
+            theme.secondary_text_color
+        } else {
+            theme.primary_text_color
+        };
+        let plain: [Furigana; 1] = [Furigana {
+            ruby: token.input_word.to_string(),
+            rt: None,
+        }];
+
+        let rt_height: f32 =
+            ui.fonts_mut(|f| f.row_height(&egui::FontId::proportional(theme.tiny_text_size)));
+        let mut galleys = Vec::new();
+        let mut total_width: f32 = 0.0;
+        let mut text_height: f32 = 0.0;
+        for furigana in reading.unwrap_or(&plain) {
+            let main_galley: Arc<egui::Galley> = ui.fonts_mut(|f| {
+                f.layout_no_wrap(
+                    furigana.ruby.to_string(),
+                    egui::FontId::proportional(theme.primary_text_size),
+                    text_color,
+                )
+            });
+            let rt_galley: Option<Arc<egui::Galley>> = furigana.rt.as_ref().map(|rt| {
+                ui.fonts_mut(|f| {
+                    f.layout_no_wrap(
+                        rt.to_string(),
+                        egui::FontId::proportional(theme.tiny_text_size),
+                        theme.light_text_color,
+                    )
+                })
+            });
+
+            let width: f32 = rt_galley.as_ref().map_or(main_galley.size().x, |rt| {
+                rt.size().x.max(main_galley.size().x)
+            });
+            total_width += width;
+            text_height = text_height.max(main_galley.size().y);
+            galleys.push((main_galley, rt_galley, width));
+        }
+
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(total_width, rt_height + vertical_gap + text_height),
+            egui::Sense::click(),
+        );
+        let text_top: f32 = rect.top() + rt_height + vertical_gap;
+        let mut current_x: f32 = rect.left();
+        for (main_galley, rt_galley, width) in galleys {
+            if let Some(rt_galley) = rt_galley {
+                let rt_pos = egui::pos2(current_x + (width - rt_galley.size().x) * 0.5, rect.top());
+                ui.painter().galley(rt_pos, rt_galley, Color32::PLACEHOLDER);
+            }
+            let main_pos = egui::pos2(current_x + (width - main_galley.size().x) * 0.5, text_top);
+            ui.painter()
+                .galley(main_pos, main_galley, Color32::PLACEHOLDER);
+            current_x += width;
+        }
+        if token.is_valid() {
+            ui.painter().hline(
+                rect.x_range(),
+                rect.bottom() - 1.0,
+                egui::Stroke::new(1.0, text_color),
+            );
+        }
+
+        response
+    }
+
+    /// Right click menu of a token, to fix words the tokenizer got wrong.
+    fn display_token_menu(
+        ui: &mut egui::Ui,
+        tokens: &[Token],
+        idx: usize,
+        can_undo_correction: bool,
+        action: &mut Option<TokenAction>,
+    ) {
+        if ui
+            .add_enabled(idx > 0, egui::Button::new("Merge with previous word"))
+            .clicked()
+        {
+            *action = Some(TokenAction::Merge(idx - 1));
+            ui.close();
+        }
+        if ui
+            .add_enabled(
+                idx + 1 < tokens.len(),
+                egui::Button::new("Merge with next word"),
+            )
+            .clicked()
+        {
+            *action = Some(TokenAction::Merge(idx));
+            ui.close();
+        }
+
+        let chars: Vec<char> = tokens[idx].input_word.chars().collect();
+        if chars.len() > 1 {
+            ui.menu_button("Split", |ui| {
+                for at in 1..chars.len() {
+                    let first: String = chars[..at].iter().collect();
+                    let second: String = chars[at..].iter().collect();
+                    if ui.button(format!("{first} | {second}")).clicked() {
+                        *action = Some(TokenAction::Split(idx, at));
+                        ui.close();
+                    }
+                }
+            });
+        }
+
+        ui.separator();
+        if ui
+            .add_enabled(
+                can_undo_correction,
+                egui::Button::new("Undo last correction"),
+            )
+            .clicked()
+        {
+            *action = Some(TokenAction::UndoCorrection);
+            ui.close();
         }
-        return clicked_index;
     }
 }
 
@@ -414,6 +1808,11 @@ impl eframe::App for MyApp {
             ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
         }
 
+        self.receive_popups(ctx);
+        self.receive_preview();
+        self.handle_keyboard(ctx);
+
+        let theme: Theme = self.theme;
         let main_frame = self.main_frame.unwrap();
 
         egui::CentralPanel::default()
@@ -421,11 +1820,28 @@ impl eframe::App for MyApp {
             .show(ctx, |ui| {
                 let footer_height = 42.0;
 
+                if self.config.queue
+                    && let Some(queue_index) = self.display_queue(ui)
+                {
+                    self.show_queued(queue_index);
+                }
+
                 let curr_sentence: String = String::from(&self.sentence);
+                let mut plugin_to_load: Option<usize> = None;
+                let mut history_step: Option<bool> = None; // true to go forward
+                let mut token_action: Option<TokenAction> = None;
+                let can_undo_correction: bool = !self.corrections.is_empty();
+                let mut search_toggled: bool = false;
                 match &(*self.plugin_state.lock().unwrap()) {
                     PluginState::Ready(plugin) => {
                         let tokens: &Vec<Token> = plugin.get_tokens();
 
+                        if self.selected_token_index.is_none()
+                            && let Some(idx) = self.restored_token_index.take()
+                            && tokens.get(idx).is_some_and(Token::is_valid)
+                        {
+                            self.selected_token_index = Some(idx);
+                        }
                         if self.selected_token_index.is_none() {
                             let mut first_valid_idx: usize = 0;
                             let mut curr_idx: usize = 0;
@@ -439,6 +1855,18 @@ impl eframe::App for MyApp {
                             self.selected_token_index = Some(first_valid_idx);
                         }
                         let selected_token_idx: usize = self.selected_token_index.unwrap();
+                        let readings: Vec<Option<Vec<Furigana>>> = if self.config.furigana {
+                            Self::token_readings(
+                                &mut self.lookup_cache,
+                                plugin.as_ref(),
+                                tokens,
+                                self.available_plugins[self.active_plugin_index]
+                                    .capabilities
+                                    .offline,
+                            )
+                        } else {
+                            Vec::new()
+                        };
 
                         ui.vertical(|ui| {
                             ui.horizontal(|ui| {
@@ -458,7 +1886,7 @@ impl eframe::App for MyApp {
                                                                 )
                                                                 .desired_width(f32::INFINITY)
                                                                 .font(egui::FontId::monospace(
-                                                                    PRIMARY_TEXT_SIZE,
+                                                                    theme.primary_text_size,
                                                                 ))
                                                                 .desired_rows(1),
                                                             );
@@ -473,17 +1901,34 @@ impl eframe::App for MyApp {
                                                             {
                                                                 res.request_focus();
                                                             }
+                                                            // Tokens of the text so far
+                                                            ui.horizontal_wrapped(|ui| {
+                                                                if let Some(action) =
+                                                                    Self::display_token_header(
+                                                                        ui,
+                                                                        &theme,
+                                                                        tokens,
+                                                                        &readings,
+                                                                        selected_token_idx,
+                                                                        can_undo_correction,
+                                                                    )
+                                                                {
+                                                                    token_action = Some(action);
+                                                                }
+                                                            });
                                                         } else {
                                                             ui.horizontal_wrapped(|ui| {
-                                                                if let Some(idx) =
+                                                                if let Some(action) =
                                                                     Self::display_token_header(
                                                                         ui,
+                                                                        &theme,
                                                                         tokens,
+                                                                        &readings,
                                                                         selected_token_idx,
+                                                                        can_undo_correction,
                                                                     )
                                                                 {
-                                                                    self.selected_token_index =
-                                                                        Some(idx);
+                                                                    token_action = Some(action);
                                                                 }
                                                             });
                                                         }
@@ -495,7 +1940,7 @@ impl eframe::App for MyApp {
                                                                 )
                                                                 .desired_width(f32::INFINITY)
                                                                 .font(egui::FontId::monospace(
-                                                                    PRIMARY_TEXT_SIZE,
+                                                                    theme.primary_text_size,
                                                                 ))
                                                                 .desired_rows(1),
                                                             );
@@ -510,23 +1955,40 @@ impl eframe::App for MyApp {
                                                             {
                                                                 res.request_focus();
                                                             }
+                                                            // Tokens of the text so far
+                                                            ui.horizontal(|ui| {
+                                                                if let Some(action) =
+                                                                    Self::display_token_header(
+                                                                        ui,
+                                                                        &theme,
+                                                                        tokens,
+                                                                        &readings,
+                                                                        selected_token_idx,
+                                                                        can_undo_correction,
+                                                                    )
+                                                                {
+                                                                    token_action = Some(action);
+                                                                }
+                                                            });
                                                         } else {
                                                             ui.horizontal(|ui| {
-                                                                if let Some(idx) =
+                                                                if let Some(action) =
                                                                     Self::display_token_header(
                                                                         ui,
+                                                                        &theme,
                                                                         tokens,
+                                                                        &readings,
                                                                         selected_token_idx,
+                                                                        can_undo_correction,
                                                                     )
                                                                 {
-                                                                    self.selected_token_index =
-                                                                        Some(idx);
+                                                                    token_action = Some(action);
                                                                 }
                                                             });
                                                         }
                                                     }
 
-                                                    ui.add_space(SPACING_SIZE);
+                                                    ui.add_space(theme.spacing_size);
                                                 });
                                             });
                                     },
@@ -539,10 +2001,25 @@ impl eframe::App for MyApp {
                                     |ui| {
                                         if ui
                                             .add(egui::Button::new(
-                                                RichText::new("\u{1F504}").size(TINY_TEXT_SIZE),
+                                                RichText::new("\u{1F50D}")
+                                                    .size(theme.tiny_text_size),
+                                            ))
+                                            .on_hover_text(
+                                                RichText::new("Search (/)")
+                                                    .size(theme.tiny_text_size),
+                                            )
+                                            .clicked()
+                                        {
+                                            search_toggled = true;
+                                        }
+                                        if ui
+                                            .add(egui::Button::new(
+                                                RichText::new("\u{1F504}")
+                                                    .size(theme.tiny_text_size),
                                             ))
                                             .on_hover_text(
-                                                RichText::new("Reverse text").size(TINY_TEXT_SIZE),
+                                                RichText::new("Reverse text")
+                                                    .size(theme.tiny_text_size),
                                             )
                                             .clicked()
                                         {
@@ -551,16 +2028,55 @@ impl eframe::App for MyApp {
                                         }
                                         if ui
                                             .add(egui::Button::new(
-                                                RichText::new("\u{270F}").size(TINY_TEXT_SIZE),
+                                                RichText::new("\u{270F}")
+                                                    .size(theme.tiny_text_size),
                                             ))
                                             .on_hover_text(
-                                                RichText::new("Edit text").size(TINY_TEXT_SIZE),
+                                                RichText::new("Edit text (E)")
+                                                    .size(theme.tiny_text_size),
                                             )
                                             .clicked()
                                         {
                                             tracing::info!("Entering edit mode.");
                                             self.edit_mode = true;
                                         }
+
+                                        let (can_go_back, can_go_forward) = {
+                                            let history = History::global().lock().unwrap();
+                                            (history.can_go_back(), history.can_go_forward())
+                                        };
+                                        if ui
+                                            .add_enabled(
+                                                can_go_forward,
+                                                egui::Button::new(
+                                                    RichText::new("\u{2192}")
+                                                        .size(theme.tiny_text_size),
+                                                ),
+                                            )
+                                            .on_hover_text(
+                                                RichText::new("Forward (Alt+\u{2192})")
+                                                    .size(theme.tiny_text_size),
+                                            )
+                                            .clicked()
+                                        {
+                                            history_step = Some(true);
+                                        }
+                                        if ui
+                                            .add_enabled(
+                                                can_go_back,
+                                                egui::Button::new(
+                                                    RichText::new("\u{2190}")
+                                                        .size(theme.tiny_text_size),
+                                                ),
+                                            )
+                                            .on_hover_text(
+                                                RichText::new("Back (Alt+\u{2190})")
+                                                    .size(theme.tiny_text_size),
+                                            )
+                                            .clicked()
+                                        {
+                                            history_step = Some(false);
+                                        }
                                     },
                                 );
                             });
@@ -568,21 +2084,173 @@ impl eframe::App for MyApp {
 
                         ui.separator();
 
+                        if let Some(search) = &mut self.search {
+                            ui.horizontal(|ui| {
+                                let close_width: f32 = theme.tiny_text_size * 2.0;
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut search.query)
+                                        .hint_text("Search a word")
+                                        .desired_width(ui.available_width() - close_width),
+                                );
+                                if std::mem::take(&mut search.focus) {
+                                    response.request_focus();
+                                }
+                                if response.lost_focus()
+                                    && ui.input(|input| input.key_pressed(egui::Key::Enter))
+                                {
+                                    search.pending = true;
+                                }
+                                if ui
+                                    .button(RichText::new("\u{2715}").size(theme.tiny_text_size))
+                                    .on_hover_text(
+                                        RichText::new("Close search (Esc)")
+                                            .size(theme.tiny_text_size),
+                                    )
+                                    .clicked()
+                                {
+                                    search_toggled = true;
+                                }
+                            });
+
+                            if std::mem::take(&mut search.pending)
+                                && !search.query.trim().is_empty()
+                            {
+                                let query: &str = search.query.trim();
+                                tracing::info!("Searching for {query}.");
+                                search.result = Some(plugin.search(query).map_err(|e| {
+                                    tracing::debug!(
+                                        "Could not search for {query} due to error: {e}"
+                                    );
+                                    e.to_string()
+                                }));
+                                self.scroll_delta = 0.0;
+                            }
+                        }
+
+                        let lookup_result: Option<&LookupResult> = match self
+                            .search
+                            .as_ref()
+                            .and_then(|search| search.result.as_ref())
+                        {
+                            Some(search_result) => search_result.as_ref().ok(),
+                            None => {
+                                let selected_token: &Token = &tokens[selected_token_idx];
+                                self.lookup_cache
+                                    .entry(selected_token_idx)
+                                    .or_insert_with(|| {
+                                        Self::lookup_token(plugin.as_ref(), selected_token)
+                                    })
+                                    .as_ref()
+                                    .ok()
+                            }
+                        };
+                        let no_search_results: Option<&str> = self
+                            .search
+                            .as_ref()
+                            .filter(|search| search.result.is_some())
+                            .filter(|_| {
+                                lookup_result.is_none_or(|result| {
+                                    result.terms.is_empty() && result.kanji.is_empty()
+                                })
+                            })
+                            .map(|search| search.query.trim());
+
+                        Self::display_forms(ui, &theme, lookup_result);
+
                         let center_height = ui.available_height() - footer_height;
                         egui::ScrollArea::vertical()
                             .id_salt("plugin_display_section")
                             .max_height(center_height)
                             .auto_shrink(false)
                             .show(ui, |ui| {
-                                plugin.display_token(
-                                    ctx,
-                                    &main_frame,
-                                    self,
-                                    ui,
-                                    &tokens[selected_token_idx],
-                                );
+                                if self.scroll_delta != 0.0 {
+                                    ui.scroll_with_delta(egui::vec2(
+                                        0.0,
+                                        std::mem::take(&mut self.scroll_delta),
+                                    ));
+                                }
+                                if let Some(lookup_result) = lookup_result {
+                                    ui.indent("scroll_indent", |ui| {
+                                        Self::display_terms(ui, &theme, &lookup_result.terms);
+                                        Self::display_kanji(ui, &theme, &lookup_result.kanji);
+                                    });
+                                }
+                                if let Some(query) = no_search_results {
+                                    ui.label(
+                                        RichText::new(format!("No results for {query}."))
+                                            .size(theme.small_text_size)
+                                            .color(theme.secondary_text_color),
+                                    );
+                                }
                             });
                     }
+                    PluginState::Failed(error) => {
+                        let center_height = ui.available_height() - footer_height;
+                        ui.allocate_ui_with_layout(
+                            egui::vec2(ui.available_width(), center_height),
+                            egui::Layout::top_down(egui::Align::Center),
+                            |ui| {
+                                let failed_plugin: &PluginDescriptor =
+                                    &self.available_plugins[self.active_plugin_index];
+
+                                ui.add_space(theme.spacing_size * 2.0);
+                                ui.label(RichText::new(format!(
+                                    "Could not load plugin {}.",
+                                    failed_plugin.name
+                                )));
+                                ui.add_space(theme.spacing_size * 0.5);
+                                ui.add(egui::Label::new(
+                                    RichText::new(error)
+                                        .size(theme.tiny_text_size)
+                                        .color(theme.secondary_text_color),
+                                ));
+                                if !failed_plugin.capabilities.offline {
+                                    ui.label(
+                                        RichText::new(
+                                            "This plugin needs an active internet connection.",
+                                        )
+                                        .size(theme.tiny_text_size)
+                                        .color(theme.secondary_text_color),
+                                    );
+                                }
+                                ui.add_space(theme.spacing_size);
+
+                                if ui
+                                    .button(RichText::new("Retry").size(theme.small_text_size))
+                                    .clicked()
+                                {
+                                    plugin_to_load = Some(self.active_plugin_index);
+                                }
+
+                                if self.available_plugins.len() > 1 {
+                                    ui.add_space(theme.spacing_size);
+                                    ui.label(
+                                        RichText::new("Or try switching to another plugin:")
+                                            .size(theme.tiny_text_size)
+                                            .color(theme.secondary_text_color),
+                                    );
+                                    for (idx, plugin) in self.available_plugins.iter().enumerate() {
+                                        if idx == self.active_plugin_index {
+                                            continue;
+                                        }
+                                        if ui
+                                            .button(
+                                                RichText::new(plugin.name)
+                                                    .size(theme.small_text_size),
+                                            )
+                                            .on_hover_text(
+                                                RichText::new(plugin.description)
+                                                    .size(theme.tiny_text_size),
+                                            )
+                                            .clicked()
+                                        {
+                                            plugin_to_load = Some(idx);
+                                        }
+                                    }
+                                }
+                            },
+                        );
+                    }
                     _ => {
                         let center_height = ui.available_height() - footer_height;
                         ui.allocate_ui_with_layout(
@@ -625,13 +2293,46 @@ impl eframe::App for MyApp {
                         //ctx.request_repaint();
                     }
                 }
+                if let Some(idx) = plugin_to_load {
+                    self.try_load_plugin(idx, true);
+                }
+                if let Some(forward) = history_step {
+                    self.step_history(forward);
+                }
+                match token_action {
+                    Some(TokenAction::Select(idx)) => {
+                        self.selected_token_index = Some(idx);
+                        if let Some(search) = &mut self.search {
+                            search.result = None;
+                        }
+                    }
+                    Some(TokenAction::Merge(idx)) => self.correct_tokens(idx, Correction::merge),
+                    Some(TokenAction::Split(idx, at)) => {
+                        self.correct_tokens(idx, |tokens, idx| Correction::split(tokens, idx, at))
+                    }
+                    Some(TokenAction::UndoCorrection) => self.undo_correction(),
+                    None => {}
+                }
+                if search_toggled {
+                    if self.search.is_some() {
+                        self.close_search();
+                    } else {
+                        self.open_search();
+                    }
+                }
+                if let Some(idx) = self.selected_token_index {
+                    History::global()
+                        .lock()
+                        .unwrap()
+                        .select_token(&self.sentence, idx);
+                }
 
                 ui.allocate_ui_with_layout(
                     egui::Vec2::new(ui.available_width(), footer_height),
                     egui::Layout::left_to_right(egui::Align::Center),
                     |ui| {
                         // Calculate right-side bar width
-                        let button_width: f32 = SPACING_SIZE * 2.0;
+                        let button_width: f32 = theme.spacing_size * 2.0;
                         let button_spacing: f32 = ui.spacing().item_spacing.x;
                         let num_buttons: f32 = 3.0;
                         let fixed_area_width = (button_width * num_buttons as f32)
@@ -652,8 +2353,12 @@ impl eframe::App for MyApp {
                                         if ui
                                             .add(egui::Button::selectable(
                                                 self.active_plugin_index == idx,
-                                                RichText::new(active_plugin.name()),
+                                                RichText::new(active_plugin.name),
                                             ))
+                                            .on_hover_text(
+                                                RichText::new(active_plugin.description)
+                                                    .size(theme.tiny_text_size),
+                                            )
                                             .clicked()
                                         {
                                             clicked_idx = Some(idx);
@@ -669,45 +2374,54 @@ impl eframe::App for MyApp {
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             if ui
-                                .add(egui::Button::new(RichText::new("⚙").size(SMALL_TEXT_SIZE)))
-                                .on_hover_text(
-                                    RichText::new("Not yet implemented").size(TINY_TEXT_SIZE),
-                                )
+                                .add(egui::Button::new(
+                                    RichText::new("⚙").size(theme.small_text_size),
+                                ))
+                                .on_hover_text(RichText::new("Settings").size(theme.tiny_text_size))
                                 .clicked()
                             {
                                 // Settings button
+                                if self.settings.is_some() {
+                                    self.settings = None;
+                                } else {
+                                    self.open_settings();
+                                }
                             }
                             if ui
                                 .add(egui::Button::new(
-                                    RichText::new("\u{1f4cb}").size(SMALL_TEXT_SIZE),
+                                    RichText::new("\u{1f4cb}").size(theme.small_text_size),
                                 ))
                                 .on_hover_text(
-                                    RichText::new("Copy input to clipboard").size(TINY_TEXT_SIZE),
+                                    RichText::new("Copy input to clipboard (C)")
+                                        .size(theme.tiny_text_size),
                                 )
                                 .clicked()
                             {
                                 // Copy button
-                                let sentence: String = self.sentence.to_owned();
-                                std::thread::spawn(|| {
-                                    tracing::debug!("Trying to copy input text to clipboard.");
-                                    let mut clipboard: arboard::Clipboard =
-                                        arboard::Clipboard::new().unwrap();
-                                    clipboard.set_text(sentence).unwrap();
-                                    std::thread::sleep(std::time::Duration::from_secs(1));
-                                    drop(clipboard); // since clipboard is dropped here, linux users need a clipboard manager to retain data
-                                    tracing::debug!("Successfully copied input text to clipboard.");
-                                });
+                                copy_to_clipboard(self.sentence.to_owned());
                             }
+                            let open_hint: &str = if self.available_plugins
+                                [self.active_plugin_index]
+                                .capabilities
+                                .web_search
+                            {
+                                "Open in Web"
+                            } else {
+                                "Open attributions"
+                            };
                             if ui
-                                .add(egui::Button::new(RichText::new("ℹ").size(SMALL_TEXT_SIZE)))
-                                .on_hover_text(RichText::new("Open in Web").size(TINY_TEXT_SIZE))
+                                .add(egui::Button::new(
+                                    RichText::new("ℹ").size(theme.small_text_size),
+                                ))
+                                .on_hover_text(RichText::new(open_hint).size(theme.tiny_text_size))
                                 .clicked()
                             {
                                 // Special button
                                 if let PluginState::Ready(plugin) =
                                     &(*self.plugin_state.lock().unwrap())
+                                    && let Some(url) = plugin.open_url()
                                 {
-                                    plugin.open(ctx);
+                                    ctx.open_url(egui::OpenUrl::new_tab(url));
                                 }
                             }
                         });
@@ -716,14 +2430,36 @@ impl eframe::App for MyApp {
 
                 if curr_sentence != self.sentence {
                     self.was_edited = true;
+                    if self.edit_mode {
+                        self.edited_at = Some(Instant::now());
+                    }
+                }
+                if self.edit_mode
+                    && let Some(edited_at) = self.edited_at
+                {
+                    let waited: Duration = edited_at.elapsed();
+                    if waited < EDIT_PREVIEW_DELAY {
+                        ctx.request_repaint_after(EDIT_PREVIEW_DELAY - waited);
+                    } else if !self.sentence.trim().is_empty() {
+                        self.edited_at = None;
+                        self.load_preview(ctx);
+                    }
                 }
                 if self.was_edited && !self.edit_mode {
                     self.was_edited = false;
+                    self.edited_at = None;
                     if self.sentence.is_empty() {
                         self.sentence = String::from(" ");
                     }
-                    self.try_load_plugin(self.active_plugin_index, true);
+                    if self.sentence != self.loaded_sentence || !self.is_ready() {
+                        self.try_load_plugin(self.active_plugin_index, true);
+                    } else if !self.sentence.trim().is_empty() {
+                        // Already tokenized while typing
+                        History::global().lock().unwrap().push(&self.sentence);
+                    }
                 }
             });
+
+        self.display_settings(ctx);
     }
 }
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use crate::plugin::PluginRegistry;
+use crate::theme::ThemeConfig;
+use crate::watch_filter::{WatchFilter, WatchFilters};
+
+// Indices are the OCR model numbers used everywhere else
+pub const OCR_ENGINES: [&str; 2] = ["tesseract", "manga-ocr"];
+// Zoom factors the window can be scaled by
+pub const TEXT_SCALES: RangeInclusive<f32> = 0.5..=3.0;
+
+/*
+Every setting is optional, command line arguments take precedence over the config file. Saving
+in the settings panel rewrites the file:
+
+[window]
+width = 450
+height = 450
+font = "Noto Sans CJK JP"
+text_scale = 1.0
+at_mouse = false
+full_text = false
+furigana = false
+tray = false
+
+[plugins]
+initial = "kihon"
+order = ["kihon", "jotoba"]     # plugins not listed keep their order after the listed ones
+
+[kihon]
+pitch_accents = "accents.txt"   # Kanjium accents.txt or a Yomitan zip, relative to this file
+
+[yomitan]
+dictionaries = ["jitendex.zip"] # zip files, looked up in this order, relative to this file
+
+[ocr]
+engine = "tesseract"            # or "manga-ocr"
+
+[tokens]
+remember_corrections = false    # apply words split or merged by hand in later popups
+
+[watch]
+queue = false
+min_length = 2
+max_length = 200
+ignore = ["^https?://"]
+debounce_ms = 0
+min_image_size = 32
+allow_sources = ["firefox"]
+
+[theme]
+preset = "dark"                 # or "light", "high-contrast"
+
+[theme.colors]                  # hex colors, override the preset
+primary_background = "#1e1e1e"
+secondary_background = "#323232"
+primary_text = "#ffffff"
+secondary_text = "#a0a0a0"
+light_text = "#dcdcdc"
+error_text = "#e65a5a"
+
+[theme.sizes]                   # override the preset
+big_text = 24.0
+primary_text = 20.0
+small_text = 18.0
+tiny_text = 14.0
+spacing = 10.0
+corner_radius = 4
+*/
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub window: WindowConfig,
+    pub plugins: PluginConfig,
+    pub ocr: OcrConfig,
+    pub tokens: TokenConfig,
+    pub kihon: KihonConfig,
+    pub yomitan: YomitanConfig,
+    pub watch: WatchConfig,
+    pub theme: ThemeConfig,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    pub font: Option<String>,
+    pub text_scale: Option<f32>,
+    pub at_mouse: bool,
+    pub full_text: bool,
+    pub furigana: bool,
+    pub tray: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PluginConfig {
+    pub initial: Option<String>,
+    pub order: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OcrConfig {
+    pub engine: Option<String>,
+}
+
+impl OcrConfig {
+    /// Index of the configured engine in `OCR_ENGINES`, Tesseract if there is none.
+    pub fn model(&self) -> usize {
+        self.engine
+            .as_ref()
+            .and_then(|engine| OCR_ENGINES.iter().position(|name| name == engine))
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TokenConfig {
+    pub remember_corrections: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KihonConfig {
+    pub pitch_accents: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct YomitanConfig {
+    pub dictionaries: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchConfig {
+    pub queue: bool,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub ignore: Vec<String>,
+    pub debounce_ms: Option<u64>,
+    pub min_image_size: Option<u32>,
+    pub allow_sources: Vec<String>,
+}
+
+impl WatchConfig {
+    pub fn filters(&self) -> WatchFilters {
+        WatchFilters {
+            min_length: self.min_length,
+            max_length: self.max_length,
+            ignore_patterns: self.ignore.clone(),
+            debounce_ms: self.debounce_ms,
+            min_image_size: self.min_image_size,
+            allowed_sources: self.allow_sources.clone(),
+        }
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("popup_dictionary").join("config.toml"))
+}
+
+/// Resolves a path given in the config file. Relative paths are relative to the config file.
+pub fn resolve_path(configured_path: &str) -> PathBuf {
+    let configured_path: PathBuf = PathBuf::from(configured_path);
+    match path() {
+        Some(config_path) if configured_path.is_relative() => config_path
+            .parent()
+            .map_or(configured_path.clone(), |config_dir| {
+                config_dir.join(&configured_path)
+            }),
+        _ => configured_path,
+    }
+}
+
+/// Reads the config file. A missing config file is the same as an empty one.
+pub fn load() -> Result<ConfigFile, Box<dyn Error>> {
+    let Some(path) = path() else {
+        return Ok(ConfigFile::default());
+    };
+
+    let content: String = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(ConfigFile::default()),
+        Err(e) => return Err(Box::from(format!("Could not read {}: {e}", path.display()))),
+    };
+
+    toml::from_str(&content).map_err(|e| Box::from(format!("{}: {e}", path.display())))
+}
+
+pub fn save(config: &ConfigFile) -> Result<(), Box<dyn Error>> {
+    let Some(path) = path() else {
+        return Err(Box::from(
+            "No valid config path found in environment variables.",
+        ));
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(&path, toml::to_string(config)?)?;
+    tracing::info!("Saved config file {}.", path.display());
+
+    Ok(())
+}
+
+impl ConfigFile {
+    /// Checks values that can't be checked while parsing. Plugin names are checked against the
+    /// global plugin registry.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        for (name, size) in [("width", self.window.width), ("height", self.window.height)] {
+            if let Some(size) = size
+                && size < 100
+            {
+                return Err(Box::from(format!(
+                    "window.{name} must be at least 100 pixels, got {size}."
+                )));
+            }
+        }
+        if let Some(text_scale) = self.window.text_scale
+            && !TEXT_SCALES.contains(&text_scale)
+        {
+            return Err(Box::from(format!(
+                "window.text_scale must be between {} and {}, got {text_scale}.",
+                TEXT_SCALES.start(),
+                TEXT_SCALES.end()
+            )));
+        }
+        if self
+            .window
+            .font
+            .as_ref()
+            .is_some_and(|font| font.is_empty())
+        {
+            return Err(Box::from("window.font must not be empty."));
+        }
+
+        if let Some(initial) = &self.plugins.initial
+            && PluginRegistry::global().get(initial).is_none()
+        {
+            return Err(Box::from(format!(
+                "Unknown plugin {initial} in plugins.initial."
+            )));
+        }
+
+        if let Some(engine) = &self.ocr.engine
+            && !OCR_ENGINES.contains(&engine.as_str())
+        {
+            return Err(Box::from(format!(
+                "Unknown OCR engine {engine} in ocr.engine. Available: {}.",
+                OCR_ENGINES.join(", ")
+            )));
+        }
+
+        if let (Some(min_length), Some(max_length)) = (self.watch.min_length, self.watch.max_length)
+            && min_length > max_length
+        {
+            return Err(Box::from(
+                "watch.min_length must not be greater than watch.max_length.",
+            ));
+        }
+        WatchFilter::new(self.watch.filters())?;
+        self.theme.resolve()?;
+
+        Ok(())
+    }
+}
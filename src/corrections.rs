@@ -0,0 +1,260 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::plugin::{Plugin, Token, Validity};
+
+/// Words of the input text split or merged by hand, because the tokenizer got them wrong.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    pub from: Vec<String>, // input words as the tokenizer gave them
+    pub to: Vec<String>,   // input words they are replaced with, together the same text
+}
+
+impl Correction {
+    /// Merges the token at `index` with the one after it.
+    pub fn merge(tokens: &[Token], index: usize) -> Option<Self> {
+        let [first, second] = tokens.get(index..index + 2)? else {
+            return None;
+        };
+
+        Some(Self {
+            from: vec![first.input_word.to_owned(), second.input_word.to_owned()],
+            to: vec![format!("{}{}", first.input_word, second.input_word)],
+        })
+    }
+
+    /// Splits the token at `index` in front of its `at`th character.
+    pub fn split(tokens: &[Token], index: usize, at: usize) -> Option<Self> {
+        let word: &str = &tokens.get(index)?.input_word;
+        let (byte_index, _) = word.char_indices().nth(at).filter(|(idx, _)| *idx > 0)?;
+        let (first, second) = word.split_at(byte_index);
+
+        Some(Self {
+            from: vec![word.to_string()],
+            to: vec![first.to_string(), second.to_string()],
+        })
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.from.is_empty()
+            && !self.to.iter().any(String::is_empty)
+            && self.from.concat() == self.to.concat()
+    }
+}
+
+/// Returns the tokens with every correction applied, in the order they were made. Corrected
+/// tokens have no deinflection, plugins look them up through their usual fallbacks.
+pub fn apply(tokens: &[Token], corrections: &[Correction]) -> Vec<Token> {
+    let mut tokens: Vec<Token> = tokens.to_vec();
+    for correction in corrections {
+        let length: usize = correction.from.len();
+        let mut idx: usize = 0;
+        while idx + length <= tokens.len() {
+            let matches: bool = tokens[idx..idx + length]
+                .iter()
+                .map(|token| &token.input_word)
+                .eq(correction.from.iter());
+            if !matches {
+                idx += 1;
+                continue;
+            }
+
+            replace(&mut tokens, idx, correction);
+            idx += correction.to.len();
+        }
+    }
+
+    tokens
+}
+
+/// Applies a correction made from the token at `index` to that token only.
+pub fn apply_at(plugin: &mut Box<dyn Plugin>, index: usize, correction: &Correction) {
+    let mut tokens: Vec<Token> = plugin.get_tokens().clone();
+    replace(&mut tokens, index, correction);
+    plugin.set_tokens(tokens);
+}
+
+fn replace(tokens: &mut Vec<Token>, index: usize, correction: &Correction) {
+    tokens.splice(
+        index..index + correction.from.len(),
+        correction.to.iter().map(|word| corrected_token(word)),
+    );
+}
+
+pub fn apply_to_plugin(plugin: &mut Box<dyn Plugin>, corrections: &[Correction]) {
+    if corrections.is_empty() {
+        return;
+    }
+    let tokens: Vec<Token> = apply(plugin.get_tokens(), corrections);
+    plugin.set_tokens(tokens);
+}
+
+fn corrected_token(word: &str) -> Token {
+    Token {
+        input_word: word.to_string(),
+        deinflected_word: word.to_string(),
+        conjugations: Vec::new(),
+        validity: if word.trim().is_empty() {
+            Validity::INVALID
+        } else {
+            Validity::UNKNOWN
+        },
+    }
+}
+
+pub fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|path| path.join("popup_dictionary").join("corrections.json"))
+}
+
+/// Reads the remembered corrections. Invalid ones, e.g. from editing the file by hand, are
+/// skipped.
+pub fn load() -> Result<Vec<Correction>, Box<dyn Error>> {
+    let Some(path) = path() else {
+        return Ok(Vec::new());
+    };
+
+    let content: String = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Box::from(format!("Could not read {}: {e}", path.display()))),
+    };
+    let corrections: Vec<Correction> = serde_json::from_str(&content)
+        .map_err(|e| format!("Could not parse {}: {e}", path.display()))?;
+
+    Ok(corrections
+        .into_iter()
+        .filter(|correction| {
+            let is_valid: bool = correction.is_valid();
+            if !is_valid {
+                tracing::warn!("Skipping invalid correction {:?}.", correction);
+            }
+            is_valid
+        })
+        .collect())
+}
+
+fn save(corrections: &[Correction]) -> Result<(), Box<dyn Error>> {
+    let Some(path) = path() else {
+        return Err(Box::from(
+            "No valid data path found in environment variables.",
+        ));
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(&path, serde_json::to_string_pretty(corrections)?)?;
+    tracing::debug!("Saved corrections to {}.", path.display());
+
+    Ok(())
+}
+
+/// Adds a correction to the file, so it is applied in the following popups.
+pub fn remember(correction: &Correction) -> Result<(), Box<dyn Error>> {
+    let mut corrections: Vec<Correction> = load()?;
+    corrections.retain(|remembered| remembered != correction);
+    corrections.push(correction.clone());
+    save(&corrections)
+}
+
+pub fn forget(correction: &Correction) -> Result<(), Box<dyn Error>> {
+    let mut corrections: Vec<Correction> = load()?;
+    corrections.retain(|remembered| remembered != correction);
+    save(&corrections)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(words: &[&str]) -> Vec<Token> {
+        words.iter().map(|word| corrected_token(word)).collect()
+    }
+
+    fn words(tokens: &[Token]) -> Vec<&str> {
+        tokens
+            .iter()
+            .map(|token| token.input_word.as_str())
+            .collect()
+    }
+
+    fn correction(from: &[&str], to: &[&str]) -> Correction {
+        Correction {
+            from: from.iter().map(|word| word.to_string()).collect(),
+            to: to.iter().map(|word| word.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn split_outside_the_word() {
+        let tokens: Vec<Token> = tokens(&["食べる"]);
+
+        assert_eq!(Correction::split(&tokens, 0, 0), None);
+        assert_eq!(Correction::split(&tokens, 0, 3), None);
+        assert_eq!(Correction::split(&tokens, 0, 10), None);
+        assert_eq!(Correction::split(&tokens, 1, 1), None);
+    }
+
+    #[test]
+    fn split_multi_byte() {
+        let tokens: Vec<Token> = tokens(&["東京都"]);
+
+        assert_eq!(
+            Correction::split(&tokens, 0, 2),
+            Some(correction(&["東京都"], &["東京", "都"]))
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let tokens: Vec<Token> = tokens(&["東", "京", "都"]);
+
+        assert_eq!(
+            Correction::merge(&tokens, 1),
+            Some(correction(&["京", "都"], &["京都"]))
+        );
+        assert_eq!(Correction::merge(&tokens, 2), None);
+        assert_eq!(Correction::merge(&tokens, 3), None);
+    }
+
+    #[test]
+    fn apply_repeated_matches() {
+        let tokens: Vec<Token> = tokens(&["東", "京", "と", "東", "京"]);
+        let corrections: Vec<Correction> = vec![correction(&["東", "京"], &["東京"])];
+
+        assert_eq!(words(&apply(&tokens, &corrections)), ["東京", "と", "東京"]);
+    }
+
+    #[test]
+    fn apply_overlapping_matches() {
+        // Matching continues after the replacement, so a token isn't corrected twice
+        let tokens: Vec<Token> = tokens(&["あ", "あ", "あ"]);
+        let corrections: Vec<Correction> = vec![correction(&["あ", "あ"], &["ああ"])];
+
+        assert_eq!(words(&apply(&tokens, &corrections)), ["ああ", "あ"]);
+    }
+
+    #[test]
+    fn apply_in_order() {
+        // Later corrections see the tokens of earlier ones
+        let tokens: Vec<Token> = tokens(&["東京都", "に"]);
+        let corrections: Vec<Correction> = vec![
+            correction(&["東京都"], &["東京", "都"]),
+            correction(&["都", "に"], &["都に"]),
+        ];
+
+        let corrected: Vec<Token> = apply(&tokens, &corrections);
+        assert_eq!(words(&corrected), ["東京", "都に"]);
+        assert!(corrected.iter().all(|token| token.conjugations.is_empty()));
+    }
+
+    #[test]
+    fn validity() {
+        assert!(correction(&["東京都"], &["東京", "都"]).is_valid());
+        assert!(!correction(&["東京都"], &["東京"]).is_valid());
+        assert!(!correction(&["東京"], &["東京", ""]).is_valid());
+        assert!(!correction(&[], &[]).is_valid());
+    }
+}
@@ -35,7 +35,7 @@ pub fn load_main_font(ctx: &egui::Context, font: &str) {
         tracing::info!("Successfully loaded a supported font.");
 
         ctx.add_font(FontInsert::new(
-            "default font",
+            font, // fonts are only added once per name, so changing the font needs a new one
             egui::FontData::from_owned(data),
             vec![
                 InsertFontFamily {
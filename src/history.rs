@@ -0,0 +1,189 @@
+use std::sync::Mutex;
+
+const MAX_HISTORY_LENGTH: usize = 200;
+
+static HISTORY: Mutex<History> = Mutex::new(History::new());
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub sentence: String,
+    pub selected_token_index: Option<usize>,
+}
+
+/// Sentences shown since starting, so they can be gone back to. All windows of one watch mode
+/// share it, so it outlives the popup a sentence was shown in.
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    position: usize, // index of the shown entry
+}
+
+impl History {
+    const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn global() -> &'static Mutex<History> {
+        &HISTORY
+    }
+
+    /// Adds a newly shown sentence. Like in a browser, entries that were gone back from are
+    /// dropped.
+    pub fn push(&mut self, sentence: &str) {
+        if self
+            .current()
+            .is_some_and(|entry| entry.sentence == sentence)
+        {
+            return;
+        }
+
+        self.entries.truncate(self.position + 1);
+        self.entries.push(HistoryEntry {
+            sentence: sentence.to_string(),
+            selected_token_index: None,
+        });
+        if self.entries.len() > MAX_HISTORY_LENGTH {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len() - 1;
+    }
+
+    /// Remembers the selected token of the shown entry, if it is still the shown sentence.
+    pub fn select_token(&mut self, sentence: &str, token_index: usize) {
+        if let Some(entry) = self.entries.get_mut(self.position)
+            && entry.sentence == sentence
+        {
+            entry.selected_token_index = Some(token_index);
+        }
+    }
+
+    fn current(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.position)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.position + 1 < self.entries.len()
+    }
+
+    pub fn back(&mut self) -> Option<HistoryEntry> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.position -= 1;
+        self.current().cloned()
+    }
+
+    pub fn forward(&mut self) -> Option<HistoryEntry> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.position += 1;
+        self.current().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentence(entry: Option<HistoryEntry>) -> Option<String> {
+        entry.map(|entry| entry.sentence)
+    }
+
+    #[test]
+    fn back_and_forward() {
+        let mut history: History = History::new();
+        assert!(!history.can_go_back());
+        assert_eq!(history.back(), None);
+
+        history.push("一");
+        history.push("二");
+        history.push("三");
+        assert!(!history.can_go_forward());
+        assert_eq!(history.forward(), None);
+
+        assert_eq!(sentence(history.back()), Some("二".to_string()));
+        assert_eq!(sentence(history.back()), Some("一".to_string()));
+        assert_eq!(history.back(), None);
+        assert_eq!(sentence(history.forward()), Some("二".to_string()));
+        assert_eq!(sentence(history.forward()), Some("三".to_string()));
+    }
+
+    #[test]
+    fn push_after_going_back_drops_forward_entries() {
+        let mut history: History = History::new();
+        history.push("一");
+        history.push("二");
+        history.push("三");
+        history.back();
+        history.back();
+
+        history.push("四");
+        assert!(!history.can_go_forward());
+        assert_eq!(sentence(history.back()), Some("一".to_string()));
+        assert_eq!(history.back(), None);
+    }
+
+    #[test]
+    fn duplicate_push_is_skipped() {
+        let mut history: History = History::new();
+        history.push("一");
+        history.push("二");
+        history.select_token("二", 1);
+        history.push("二");
+
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(
+            history
+                .current()
+                .and_then(|entry| entry.selected_token_index),
+            Some(1)
+        );
+
+        // The entry gone back to is shown again without dropping the following ones
+        history.back();
+        history.push("一");
+        assert!(history.can_go_forward());
+    }
+
+    #[test]
+    fn select_token_of_other_sentence_is_ignored() {
+        let mut history: History = History::new();
+        history.push("一");
+        history.select_token("二", 1);
+
+        assert_eq!(
+            history
+                .current()
+                .and_then(|entry| entry.selected_token_index),
+            None
+        );
+    }
+
+    #[test]
+    fn length_is_capped() {
+        let mut history: History = History::new();
+        for idx in 0..=MAX_HISTORY_LENGTH {
+            history.push(&idx.to_string());
+        }
+
+        assert_eq!(history.entries.len(), MAX_HISTORY_LENGTH);
+        assert_eq!(history.position, MAX_HISTORY_LENGTH - 1);
+        assert_eq!(
+            sentence(history.current().cloned()),
+            Some(MAX_HISTORY_LENGTH.to_string())
+        );
+
+        let mut oldest: Option<HistoryEntry> = None;
+        while let Some(entry) = history.back() {
+            oldest = Some(entry);
+        }
+        assert_eq!(sentence(oldest), Some("1".to_string()));
+    }
+}
@@ -0,0 +1,225 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::app;
+
+/*
+Protocol used between a running instance, e.g. in watch mode, and later invocations:
+1. The client sends one line with a JSON encoded `Header`.
+2. For images, the encoded image data follows the header until the client shuts down writing.
+3. The server answers with one status line, either "ok" or "error: <message>".
+4. For headless output modes, the printed lookup follows the status line.
+*/
+#[derive(Serialize, Deserialize)]
+enum Header {
+    Text {
+        config: app::Config,
+        sentence: String,
+    },
+    Image {
+        config: app::Config,
+        ocr_model: usize,
+    },
+}
+
+/// A lookup forwarded from another invocation.
+pub struct Request {
+    pub config: app::Config,
+    pub input: Input,
+}
+
+pub enum Input {
+    Text(String),
+    Image {
+        image: DynamicImage,
+        ocr_model: usize,
+    },
+}
+
+fn socket_path() -> Result<PathBuf, Box<dyn Error>> {
+    let dir: PathBuf = match dirs::runtime_dir() {
+        Some(path) => path,
+        None => match dirs::data_dir() {
+            Some(path) => path.join("popup_dictionary"),
+            None => Err("No valid runtime or data path found in environment variables.")?,
+        },
+    };
+
+    Ok(dir.join("popup_dictionary.sock"))
+}
+
+/// Removes the socket when dropped, so later invocations stop forwarding to this instance.
+pub struct Listener {
+    path: PathBuf,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!("Could not remove socket {}: {e}", self.path.display());
+        }
+    }
+}
+
+/// Starts listening for forwarded lookups in the background and passes them to `handler`.
+/// Anything the handler writes is sent back to the invocation that forwarded the lookup.
+pub fn listen<F>(handler: F) -> Result<Listener, Box<dyn Error>>
+where
+    F: Fn(Request, &mut Vec<u8>) -> Result<(), Box<dyn Error>> + Send + Sync + 'static,
+{
+    let path: PathBuf = socket_path()?;
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(Box::from(format!(
+                "Another instance is already listening on {}.",
+                path.display()
+            )));
+        }
+        // Left behind by an instance that did not shut down cleanly
+        std::fs::remove_file(&path)?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener: UnixListener = UnixListener::bind(&path)?;
+    tracing::info!("Listening for other invocations on {}.", path.display());
+
+    let handler = Arc::new(handler);
+    let socket = Listener { path };
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let handler = Arc::clone(&handler);
+                    std::thread::spawn(move || {
+                        if let Err(e) = serve(stream, handler.as_ref()) {
+                            tracing::warn!("Could not serve forwarded lookup due to error: {e}");
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("Could not accept connection due to error: {e}");
+                }
+            }
+        }
+    });
+
+    Ok(socket)
+}
+
+fn serve<F>(stream: UnixStream, handler: &F) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(Request, &mut Vec<u8>) -> Result<(), Box<dyn Error>>,
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line: String = String::new();
+    reader.read_line(&mut line)?;
+
+    let request: Request = match serde_json::from_str(&line)? {
+        Header::Text { config, sentence } => Request {
+            config,
+            input: Input::Text(sentence),
+        },
+        Header::Image { config, ocr_model } => {
+            let mut image_data: Vec<u8> = Vec::new();
+            reader.read_to_end(&mut image_data)?;
+            Request {
+                config,
+                input: Input::Image {
+                    image: image::load_from_memory(&image_data)?,
+                    ocr_model,
+                },
+            }
+        }
+    };
+    tracing::info!("Received forwarded lookup.");
+
+    let mut output: Vec<u8> = Vec::new();
+    let mut stream: UnixStream = stream;
+    match handler(request, &mut output) {
+        Ok(()) => {
+            writeln!(stream, "ok")?;
+            stream.write_all(&output)?;
+        }
+        Err(e) => {
+            tracing::warn!("Forwarded lookup failed due to error: {e}");
+            writeln!(stream, "error: {}", e.to_string().replace('\n', " "))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Forwards text to a running instance. Returns false if there is none.
+pub fn forward_text(config: &app::Config, sentence: &str) -> Result<bool, Box<dyn Error>> {
+    let header = Header::Text {
+        config: config.clone(),
+        sentence: sentence.to_string(),
+    };
+    send(&header, &[])
+}
+
+/// Forwards an image to a running instance, which runs OCR on it. Returns false if there is
+/// none.
+pub fn forward_image(
+    config: &app::Config,
+    image: &DynamicImage,
+    ocr_model: usize,
+) -> Result<bool, Box<dyn Error>> {
+    let mut image_data: Vec<u8> = Vec::new();
+    image.write_to(
+        &mut std::io::Cursor::new(&mut image_data),
+        image::ImageFormat::Png,
+    )?;
+
+    let header = Header::Image {
+        config: config.clone(),
+        ocr_model,
+    };
+    send(&header, &image_data)
+}
+
+fn send(header: &Header, payload: &[u8]) -> Result<bool, Box<dyn Error>> {
+    let path: PathBuf = socket_path()?;
+    let mut stream: UnixStream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            tracing::debug!("No running instance found at {}.", path.display());
+            return Ok(false);
+        }
+        Err(e) => return Err(Box::from(e)),
+    };
+    tracing::info!(
+        "Forwarding lookup to running instance at {}.",
+        path.display()
+    );
+
+    serde_json::to_writer(&mut stream, header)?;
+    writeln!(stream)?;
+    stream.write_all(payload)?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status: String = String::new();
+    reader.read_line(&mut status)?;
+    match status.trim_end() {
+        "ok" => {}
+        "" => return Err(Box::from("Running instance closed the connection.")),
+        status => {
+            let message: &str = status.strip_prefix("error: ").unwrap_or(status);
+            return Err(Box::from(format!("Running instance failed: {message}")));
+        }
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    std::io::copy(&mut reader, &mut stdout)?;
+    stdout.flush()?;
+
+    Ok(true)
+}
@@ -11,24 +11,54 @@ use regex::Regex;
 use std::error::Error;
 use std::io::Cursor;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::OnceLock;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::Duration;
 
 use crate::app::run_app;
 use crate::manga_ocr::MangaOcr;
+use crate::output::{OutputMode, print_lookup, write_lookup};
+use crate::plugin::PluginRegistry;
+#[cfg(all(target_os = "linux", not(feature = "wayland-support")))]
+use crate::selection_watcher::{Selection, SelectionChange, SelectionWatcher};
 use crate::tesseract::{check_tesseract, ocr_image};
+use crate::watch_filter::WatchFilter;
 
 pub mod app;
+pub mod config_file;
+mod corrections;
 mod font_helper;
+mod history;
+#[cfg(target_os = "linux")]
+mod ipc;
 mod manga_ocr;
-mod plugin;
+pub mod output;
+pub mod plugin;
 mod plugins;
+#[cfg(all(target_os = "linux", not(feature = "wayland-support")))]
+mod selection_watcher;
+mod settings;
 mod tesseract;
+pub mod theme;
+pub mod watch_filter;
 mod window_helper;
 
-pub fn run(sentence: &str, config: app::Config) -> Result<(), Box<dyn Error>> {
+pub fn run(sentence: &str, config: app::Config, ocr_model: usize) -> Result<(), Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    if ipc::forward_text(&config, sentence)? {
+        tracing::info!("Lookup was handled by the running instance.");
+        return Ok(());
+    }
+
+    // Only shown in the settings, forwarded images bring their own
+    show(sentence, config, ocr_model, None)
+}
+
+fn validate(sentence: &str) -> Result<String, Box<dyn Error>> {
     let sentence: String = sentence.chars().filter(|c| !c.is_whitespace()).collect();
 
     if sentence.is_empty() {
@@ -39,10 +69,85 @@ pub fn run(sentence: &str, config: app::Config) -> Result<(), Box<dyn Error>> {
         return Err(Box::from("Input text must contain japanese text."));
     }
 
+    Ok(sentence)
+}
+
+fn show(
+    sentence: &str,
+    config: app::Config,
+    ocr_model: usize,
+    manga_ocr: Option<MangaOcr>,
+) -> Result<(), Box<dyn Error>> {
+    let sentence: String = validate(sentence)?;
+
+    if config.output != OutputMode::Window {
+        tracing::info!("Input looks good. Printing lookup results.");
+        return print_lookup(&sentence, &config);
+    }
+
     tracing::info!("Input looks good. Launching dictionary app.");
-    run_app(&sentence, config)?;
+    #[cfg(target_os = "linux")]
+    return show_listening(sentence, config, ocr_model, manga_ocr);
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (ocr_model, manga_ocr);
+        run_app(&sentence, config, None)?;
+        Ok(())
+    }
+}
 
-    Ok(())
+/// Opens the window while listening for other invocations like watch mode does, so they show
+/// their lookups in it instead of opening the database a second time. The window stays open for
+/// the lookups forwarded to it and closing it ends the instance, unless more are waiting.
+#[cfg(target_os = "linux")]
+fn show_listening(
+    sentence: String,
+    config: app::Config,
+    ocr_model: usize,
+    manga_ocr: Option<MangaOcr>,
+) -> Result<(), Box<dyn Error>> {
+    let manga_ocr: Arc<Mutex<Option<MangaOcr>>> = Arc::new(Mutex::new(manga_ocr));
+    let window: app::WindowContext = Arc::default();
+    let (sender, receiver) = mpsc::channel::<app::Popup>();
+    let sender = app::PopupSender::new(sender, Arc::clone(&window));
+    // Removes the socket once the last window is closed
+    let _listener: ipc::Listener = match listen_for_invocations(&manga_ocr, &sender) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::warn!("Not listening for other invocations due to error: {e}");
+            run_app(&sentence, config, None)?;
+            return Ok(());
+        }
+    };
+
+    let active_plugin: usize = config
+        .initial_plugin
+        .as_ref()
+        .and_then(|name| PluginRegistry::global().position(name))
+        .unwrap_or(0);
+    let watching = app::Watching {
+        popups: Arc::new(Mutex::new(receiver)),
+        window,
+        queue: Arc::default(),
+        config: Arc::new(Mutex::new(config.clone())),
+        filter: Arc::default(),
+        ocr_model: Arc::new(AtomicUsize::new(ocr_model)),
+        active_plugin: Arc::new(AtomicUsize::new(active_plugin)),
+    };
+    run_app(&sentence, config, Some(watching.clone()))?;
+    // Lookups forwarded after the window was closed
+    show_popups(&watching, false)
+}
+
+#[cfg(target_os = "linux")]
+fn listen_for_invocations(
+    manga_ocr: &Arc<Mutex<Option<MangaOcr>>>,
+    popups: &app::PopupSender,
+) -> Result<ipc::Listener, Box<dyn Error>> {
+    let manga_ocr = Arc::clone(manga_ocr);
+    let popups: app::PopupSender = popups.clone();
+    ipc::listen(move |request, output| serve_request(request, &manga_ocr, &popups, output))
 }
 
 fn contains_japanese(text: &str) -> bool {
@@ -63,7 +168,7 @@ fn contains_japanese(text: &str) -> bool {
 }
 
 #[cfg(target_os = "linux")]
-pub fn primary(config: app::Config) -> Result<(), Box<dyn Error>> {
+pub fn primary(config: app::Config, ocr_model: usize) -> Result<(), Box<dyn Error>> {
     tracing::info!("Attempting to run primary mode.");
 
     let mut clipboard: Clipboard = Clipboard::new()?;
@@ -73,11 +178,11 @@ pub fn primary(config: app::Config) -> Result<(), Box<dyn Error>> {
         .text()?;
 
     tracing::debug!("Text received from primary selection.");
-    run(&sentence, config)
+    run(&sentence, config, ocr_model)
 }
 
 #[cfg(target_os = "linux")]
-pub fn secondary(config: app::Config) -> Result<(), Box<dyn Error>> {
+pub fn secondary(config: app::Config, ocr_model: usize) -> Result<(), Box<dyn Error>> {
     tracing::info!("Attempting to run secondary mode.");
 
     let mut clipboard: Clipboard = Clipboard::new()?;
@@ -87,17 +192,17 @@ pub fn secondary(config: app::Config) -> Result<(), Box<dyn Error>> {
         .text()?;
 
     tracing::debug!("Text received from secondary selection.");
-    run(&sentence, config)
+    run(&sentence, config, ocr_model)
 }
 
-pub fn clipboard(config: app::Config) -> Result<(), Box<dyn Error>> {
+pub fn clipboard(config: app::Config, ocr_model: usize) -> Result<(), Box<dyn Error>> {
     tracing::info!("Attempting to run clipboard mode.");
 
     let mut clipboard: Clipboard = Clipboard::new()?;
     let sentence: String = clipboard.get().text()?;
 
     tracing::debug!("Text received from main clipboard.");
-    run(&sentence, config)
+    run(&sentence, config, ocr_model)
 }
 
 /*
@@ -130,13 +235,200 @@ pub fn watch(
     config: app::Config,
     paused: Arc<AtomicBool>,
     ocr_model: Arc<AtomicUsize>,
+    active_plugin: Arc<AtomicUsize>,
 ) -> Result<(), Box<dyn Error>> {
     tracing::info!("Attempting to run watch mode.");
+    run_watcher(config, paused, ocr_model, active_plugin, watch_clipboard)
+}
 
-    let mut clipboard: Clipboard = Clipboard::new()?;
-    let mut initial_content: ClipboardContent = get_clipboard_content(&mut clipboard);
+#[cfg(target_os = "linux")]
+pub fn watch_primary(
+    config: app::Config,
+    paused: Arc<AtomicBool>,
+    ocr_model: Arc<AtomicUsize>,
+    active_plugin: Arc<AtomicUsize>,
+) -> Result<(), Box<dyn Error>> {
+    tracing::info!("Attempting to run primary watch mode.");
+    run_watcher(
+        config,
+        paused,
+        ocr_model,
+        active_plugin,
+        watch_primary_selection,
+    )
+}
+
+fn run_watcher(
+    config: app::Config,
+    paused: Arc<AtomicBool>,
+    ocr_model: Arc<AtomicUsize>,
+    active_plugin: Arc<AtomicUsize>,
+    watcher: fn(Clipboard, Arc<AtomicBool>, WatchContext),
+) -> Result<(), Box<dyn Error>> {
+    let manga_ocr: Arc<Mutex<Option<MangaOcr>>> = Arc::new(Mutex::new(None));
+    let window: app::WindowContext = Arc::default();
+    let (sender, receiver) = mpsc::channel::<app::Popup>();
+    let sender = app::PopupSender::new(sender, Arc::clone(&window));
+
+    // Later invocations forward their lookups here instead of starting from scratch
+    #[cfg(target_os = "linux")]
+    let _listener: ipc::Listener = listen_for_invocations(&manga_ocr, &sender)
+        .map_err(|e| format!("Could not listen for other invocations: {e}"))?;
+
+    // Load the active plugin's resources in the background so the first popup opens quickly.
+    if let Some(descriptor) = PluginRegistry::global()
+        .all()
+        .get(active_plugin.load(Ordering::Relaxed))
+    {
+        std::thread::spawn(move || {
+            if let Err(e) = descriptor.preload() {
+                tracing::warn!("Could not preload plugin {}: {e}", descriptor.name);
+            }
+        });
+    }
+
+    // Fail early on invalid filters, later changes are checked by the settings panel
+    let filter: app::SharedWatchFilter = Arc::new(Mutex::new(Arc::new(WatchFilter::new(
+        config.filters.clone(),
+    )?)));
+
+    let clipboard: Clipboard = Clipboard::new()?;
+    let shared_config: Arc<Mutex<app::Config>> = Arc::new(Mutex::new(config));
+    let context = WatchContext {
+        config: Arc::clone(&shared_config),
+        filter: Arc::clone(&filter),
+        ocr_model: Arc::clone(&ocr_model),
+        active_plugin: Arc::clone(&active_plugin),
+        manga_ocr,
+        popups: sender,
+    };
+    std::thread::spawn(move || watcher(clipboard, paused, context));
+
+    // Windows have to be opened on the main thread. While a window is open, it takes the
+    // following lookups itself, so only one window is opened until it is closed again.
+    let watching = app::Watching {
+        popups: Arc::new(Mutex::new(receiver)),
+        window,
+        queue: Arc::default(),
+        config: shared_config,
+        filter,
+        ocr_model,
+        active_plugin,
+    };
+    show_popups(&watching, true)
+}
 
-    let mut manga_ocr: Option<MangaOcr> = None;
+/// Opens a window for each popup, waiting for the next one if `wait` is set. Otherwise it returns
+/// once no popup is left after a window was closed.
+fn show_popups(watching: &app::Watching, wait: bool) -> Result<(), Box<dyn Error>> {
+    loop {
+        let popup: Option<app::Popup> = {
+            let popups = watching
+                .popups
+                .lock()
+                .map_err(|_| "Popup receiver lock is poisoned.")?;
+            if wait {
+                popups.recv().ok()
+            } else {
+                popups.try_recv().ok()
+            }
+        };
+        let Some((sentence, config)) = popup else {
+            break;
+        };
+
+        if config.output != OutputMode::Window {
+            if let Err(e) = print_lookup(&sentence, &config) {
+                tracing::warn!("Failed while printing lookup in watch mode due to error: {e}");
+            }
+            continue;
+        }
+
+        tracing::info!("Launching dictionary app.");
+        if let Err(e) = run_app(&sentence, config, Some(watching.clone())) {
+            tracing::warn!("Failed while running the dictionary app due to error: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+// Everything needed to turn new clipboard content into a popup
+struct WatchContext {
+    config: Arc<Mutex<app::Config>>, // can be changed in the settings panel while watching
+    filter: app::SharedWatchFilter,  // compiled from the filters of config
+    ocr_model: Arc<AtomicUsize>,
+    active_plugin: Arc<AtomicUsize>,
+    manga_ocr: Arc<Mutex<Option<MangaOcr>>>,
+    popups: app::PopupSender,
+}
+
+fn watch_clipboard(mut clipboard: Clipboard, paused: Arc<AtomicBool>, context: WatchContext) {
+    #[cfg(all(target_os = "linux", not(feature = "wayland-support")))]
+    match SelectionWatcher::new(&[Selection::Clipboard]) {
+        Ok(watcher) => {
+            // Clipboard managers take over the clipboard with the same content again
+            let mut last_content: ClipboardContent = get_clipboard_content(&mut clipboard);
+
+            tracing::info!("Watching...");
+            loop {
+                let mut change: SelectionChange = match watcher.wait_for_change() {
+                    Ok(change) => change,
+                    Err(e) => {
+                        tracing::warn!("Stopped receiving clipboard events due to error: {e}");
+                        break;
+                    }
+                };
+                let filter: Arc<WatchFilter> = context.filter();
+                let debounce: Duration = filter.debounce(Duration::ZERO);
+                // Only the last of several quick copies is looked up
+                if !debounce.is_zero() {
+                    loop {
+                        std::thread::sleep(debounce);
+                        match watcher.take_pending_change() {
+                            Ok(Some(latest_change)) => change = latest_change,
+                            Ok(None) => break,
+                            Err(e) => {
+                                tracing::warn!("Could not check for clipboard events: {e}");
+                                break;
+                            }
+                        }
+                    }
+                }
+                if paused.load(Ordering::Relaxed) {
+                    // Content copied while paused is not looked up after resuming either
+                    last_content = get_clipboard_content(&mut clipboard);
+                    continue;
+                }
+
+                let content: ClipboardContent = get_clipboard_content(&mut clipboard);
+                if !clipboard_content_differs(&last_content, &content) {
+                    tracing::debug!("Ignoring clipboard owner change without new content.");
+                    continue;
+                }
+                last_content = content;
+
+                let source: Option<String> = watcher.window_class(change.owner);
+                tracing::debug!("Clipboard owner window class is {:?}.", source);
+                if !filter.allows_source(source.as_deref()) {
+                    tracing::debug!("Ignoring clipboard content from a source not allowed.");
+                    continue;
+                }
+
+                tracing::info!("New clipboard content detected.");
+                handle_clipboard_content(&last_content, &context);
+            }
+        }
+        Err(e) => {
+            tracing::warn!("Could not watch clipboard events due to error: {e}");
+        }
+    }
+
+    tracing::info!("Falling back to polling the clipboard.");
+    if context.filter().has_source_allowlist() {
+        tracing::warn!("The source allowlist can't be applied while polling the clipboard.");
+    }
+    let mut initial_content: ClipboardContent = get_clipboard_content(&mut clipboard);
 
     tracing::info!("Watching...");
     let mut was_paused = false;
@@ -154,81 +446,258 @@ pub fn watch(
             was_paused = false;
         }
 
-        let current_content: ClipboardContent = get_clipboard_content(&mut clipboard);
+        let mut current_content: ClipboardContent = get_clipboard_content(&mut clipboard);
         if clipboard_content_differs(&initial_content, &current_content) {
+            let debounce: Duration = context.filter().debounce(Duration::ZERO);
+            // Only the last of several quick copies is looked up
+            if !debounce.is_zero() {
+                loop {
+                    std::thread::sleep(debounce);
+                    let latest_content: ClipboardContent = get_clipboard_content(&mut clipboard);
+                    if !clipboard_content_differs(&current_content, &latest_content) {
+                        break;
+                    }
+                    current_content = latest_content;
+                }
+            }
+
             tracing::info!("New clipboard content detected.");
+            handle_clipboard_content(&current_content, &context);
 
-            if let Some(image) = current_content.image {
-                tracing::debug!("Found image data in main clipboard.");
-
-                let image_data = image.clone();
-                let mut success: bool = false;
-                match ImageReader::new(Cursor::new(image_data.bytes)).with_guessed_format() {
-                    Ok(data) => match data.decode() {
-                        Ok(dynamic_image) => {
-                            success = true;
-                            if let Err(e) = ocr(
-                                dynamic_image,
-                                config.clone(),
-                                ocr_model.load(Ordering::Relaxed),
-                                &mut manga_ocr,
-                            ) {
-                                tracing::warn!(
-                                    "Failed while running OCR mode in watch mode due to error: {e}"
-                                );
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!("Could not decode image data due to error: {e}");
-                        }
-                    },
-                    Err(e) => {
-                        tracing::warn!("Could not read image data due to error: {e}");
-                    }
-                };
+            // The popup stays open while watching, so anything copied from now on is shown in it
+            initial_content = current_content;
+        }
+    }
+}
 
-                if !success {
-                    tracing::debug!("Trying to parse image data as raw pixel buffer instead.");
-
-                    let image_data = image.clone();
-                    if let Some(buffer) = ImageBuffer::<Rgba<u8>, _>::from_raw(
-                        image_data.width as u32,
-                        image_data.height as u32,
-                        image_data.bytes.into_owned(),
-                    ) {
-                        let dynamic_image = DynamicImage::ImageRgba8(buffer);
-                        if let Err(e) = ocr(
-                            dynamic_image,
-                            config.clone(),
-                            ocr_model.load(Ordering::Relaxed),
-                            &mut manga_ocr,
-                        ) {
-                            tracing::warn!(
-                                "Failed while running OCR mode in watch mode due to error: {e}"
-                            );
-                        }
-                    } else {
-                        tracing::debug!(
-                            "Image buffer not big enough for from_raw. This is weird..."
+impl WatchContext {
+    fn config(&self) -> app::Config {
+        self.config
+            .lock()
+            .map(|config| config.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    fn popup_config(&self) -> app::Config {
+        // The plugin can be switched from the tray menu while watching
+        let mut config: app::Config = self.config();
+        if let Some(plugin) = PluginRegistry::global()
+            .all()
+            .get(self.active_plugin.load(Ordering::Relaxed))
+        {
+            config.initial_plugin = Some(plugin.name.to_string());
+        }
+        config
+    }
+
+    /// Filters are replaced together with the config when settings are saved, so changed
+    /// settings apply right away.
+    fn filter(&self) -> Arc<WatchFilter> {
+        self.filter
+            .lock()
+            .map(|filter| Arc::clone(&filter))
+            .unwrap_or_else(|e| Arc::clone(&e.into_inner()))
+    }
+}
+
+fn handle_clipboard_content(content: &ClipboardContent, context: &WatchContext) {
+    let config: app::Config = context.popup_config();
+    let filter: Arc<WatchFilter> = context.filter();
+
+    if let Some(image) = &content.image {
+        tracing::debug!("Found image data in main clipboard.");
+        if !filter.allows_image(image.width as u32, image.height as u32) {
+            tracing::debug!(
+                "Ignoring image of {}x{} pixels as it is too small.",
+                image.width,
+                image.height
+            );
+            return;
+        }
+
+        let image_data = image.clone();
+        let mut success: bool = false;
+        match ImageReader::new(Cursor::new(image_data.bytes)).with_guessed_format() {
+            Ok(data) => match data.decode() {
+                Ok(dynamic_image) => {
+                    success = true;
+                    if let Err(e) = recognize_shared(
+                        dynamic_image,
+                        context.ocr_model.load(Ordering::Relaxed),
+                        &context.manga_ocr,
+                    )
+                    .and_then(|sentence| send_popup(&sentence, config.clone(), &context.popups))
+                    {
+                        tracing::warn!(
+                            "Failed while running OCR mode in watch mode due to error: {e}"
                         );
                     }
                 }
-            } else if let Some(sentence) = current_content.text {
-                tracing::debug!("Found text in main clipboard.");
-                if let Err(e) = run(&sentence, config.clone()) {
-                    tracing::warn!(
-                        "Failed while running text mode in watch mode due to error: {e}"
-                    );
+                Err(e) => {
+                    tracing::warn!("Could not decode image data due to error: {e}");
                 }
+            },
+            Err(e) => {
+                tracing::warn!("Could not read image data due to error: {e}");
             }
+        };
 
-            // Getting clipboard content again here instead of replacing with current_content
-            // makes sure that clipboard changes while app was running aren't acted on
-            initial_content = get_clipboard_content(&mut clipboard);
+        if !success {
+            tracing::debug!("Trying to parse image data as raw pixel buffer instead.");
+
+            let image_data = image.clone();
+            if let Some(buffer) = ImageBuffer::<Rgba<u8>, _>::from_raw(
+                image_data.width as u32,
+                image_data.height as u32,
+                image_data.bytes.into_owned(),
+            ) {
+                let dynamic_image = DynamicImage::ImageRgba8(buffer);
+                if let Err(e) = recognize_shared(
+                    dynamic_image,
+                    context.ocr_model.load(Ordering::Relaxed),
+                    &context.manga_ocr,
+                )
+                .and_then(|sentence| send_popup(&sentence, config.clone(), &context.popups))
+                {
+                    tracing::warn!("Failed while running OCR mode in watch mode due to error: {e}");
+                }
+            } else {
+                tracing::debug!("Image buffer not big enough for from_raw. This is weird...");
+            }
+        }
+    } else if let Some(sentence) = &content.text {
+        tracing::debug!("Found text in main clipboard.");
+        if app::is_own_copy(sentence) {
+            tracing::debug!("Ignoring text that was copied from the popup.");
+        } else if let Err(reason) = filter.check_text(sentence) {
+            tracing::debug!("Ignoring clipboard text as it is {reason}.");
+        } else if let Err(e) = send_popup(sentence, config, &context.popups) {
+            tracing::warn!("Failed while running text mode in watch mode due to error: {e}");
         }
     }
+}
 
-    Ok(())
+// How long the primary selection has to stay the same before it is looked up by default, so
+// dragging the mouse over text doesn't open a popup for every character
+#[cfg(target_os = "linux")]
+const PRIMARY_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[cfg(target_os = "linux")]
+fn watch_primary_selection(
+    mut clipboard: Clipboard,
+    paused: Arc<AtomicBool>,
+    context: WatchContext,
+) {
+    #[cfg(not(feature = "wayland-support"))]
+    let mut watcher: Option<SelectionWatcher> = match SelectionWatcher::new(&[Selection::Primary]) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            tracing::warn!("Could not watch primary selection events due to error: {e}");
+            tracing::info!("Falling back to polling the primary selection.");
+            None
+        }
+    };
+
+    #[cfg(feature = "wayland-support")]
+    if context.filter().has_source_allowlist() {
+        tracing::warn!("The source allowlist can't be applied while polling the selection.");
+    }
+
+    // Whatever is selected when starting has already been seen
+    let mut last_text: Option<String> = get_primary_text(&mut clipboard);
+
+    tracing::info!("Watching...");
+    loop {
+        // Holds the window class of the selection owner if an event was received
+        #[cfg(not(feature = "wayland-support"))]
+        let event_source: Option<Option<String>> = match &watcher {
+            Some(selection_watcher) => match selection_watcher.wait_for_change() {
+                Ok(change) => Some(selection_watcher.window_class(change.owner)),
+                Err(e) => {
+                    tracing::warn!("Stopped receiving primary selection events due to error: {e}");
+                    tracing::info!("Falling back to polling the primary selection.");
+                    if context.filter().has_source_allowlist() {
+                        tracing::warn!(
+                            "The source allowlist can't be applied while polling the selection."
+                        );
+                    }
+                    watcher = None;
+                    None
+                }
+            },
+            None => None,
+        };
+        #[cfg(feature = "wayland-support")]
+        let event_source: Option<Option<String>> = None;
+        if event_source.is_none() {
+            std::thread::sleep(Duration::from_millis(200));
+        }
+
+        if paused.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        let mut text: Option<String> = get_primary_text(&mut clipboard);
+        if text == last_text {
+            continue;
+        }
+        let filter: Arc<WatchFilter> = context.filter();
+        let debounce: Duration = filter.debounce(PRIMARY_DEBOUNCE);
+        // Wait until the selection stopped changing
+        loop {
+            std::thread::sleep(debounce);
+            let current_text: Option<String> = get_primary_text(&mut clipboard);
+            if current_text == text {
+                break;
+            }
+            text = current_text;
+        }
+        if text == last_text {
+            continue;
+        }
+        last_text = text.clone();
+
+        let Some(sentence) = text else {
+            continue;
+        };
+        if !contains_japanese(&sentence) {
+            tracing::debug!("Ignoring selected text without japanese text.");
+            continue;
+        }
+        if let Some(source) = &event_source
+            && !filter.allows_source(source.as_deref())
+        {
+            tracing::debug!("Ignoring selected text from a source not allowed.");
+            continue;
+        }
+        if let Err(reason) = filter.check_text(&sentence) {
+            tracing::debug!("Ignoring selected text as it is {reason}.");
+            continue;
+        }
+
+        tracing::info!("New primary selection detected.");
+        if let Err(e) = send_popup(&sentence, context.popup_config(), &context.popups) {
+            tracing::warn!("Failed while running primary watch mode due to error: {e}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn get_primary_text(clipboard: &mut Clipboard) -> Option<String> {
+    clipboard
+        .get()
+        .clipboard(arboard::LinuxClipboardKind::Primary)
+        .text()
+        .ok()
+}
+
+fn send_popup(
+    sentence: &str,
+    config: app::Config,
+    popups: &app::PopupSender,
+) -> Result<(), Box<dyn Error>> {
+    let sentence: String = validate(sentence)?;
+    popups.send((sentence, config))
 }
 
 fn get_clipboard_content(clipboard: &mut Clipboard) -> ClipboardContent {
@@ -260,12 +729,57 @@ fn clipboard_content_differs(first: &ClipboardContent, second: &ClipboardContent
     }
 }
 
+#[cfg(target_os = "linux")]
+fn serve_request(
+    request: ipc::Request,
+    manga_ocr: &Mutex<Option<MangaOcr>>,
+    popups: &app::PopupSender,
+    output: &mut Vec<u8>,
+) -> Result<(), Box<dyn Error>> {
+    let sentence: String = match request.input {
+        ipc::Input::Text(sentence) => sentence,
+        ipc::Input::Image { image, ocr_model } => recognize_shared(image, ocr_model, manga_ocr)?,
+    };
+
+    if request.config.output != OutputMode::Window {
+        let sentence: String = validate(&sentence)?;
+        return write_lookup(output, &sentence, &request.config);
+    }
+
+    // Windows have to be opened by watch mode on the main thread
+    send_popup(&sentence, request.config, popups)
+}
+
 pub fn ocr(
     image: DynamicImage,
     config: app::Config,
     ocr_model: usize,
     manga_ocr: &mut Option<MangaOcr>,
 ) -> Result<(), Box<dyn Error>> {
+    #[cfg(target_os = "linux")]
+    if ipc::forward_image(&config, &image, ocr_model)? {
+        tracing::info!("OCR was handled by the running instance.");
+        return Ok(());
+    }
+
+    let sentence: String = recognize(image, ocr_model, manga_ocr)?;
+    show(&sentence, config, ocr_model, manga_ocr.take())
+}
+
+fn recognize_shared(
+    image: DynamicImage,
+    ocr_model: usize,
+    manga_ocr: &Mutex<Option<MangaOcr>>,
+) -> Result<String, Box<dyn Error>> {
+    let mut manga_ocr = manga_ocr.lock().map_err(|_| "MangaOCR lock is poisoned.")?;
+    recognize(image, ocr_model, &mut manga_ocr)
+}
+
+fn recognize(
+    image: DynamicImage,
+    ocr_model: usize,
+    manga_ocr: &mut Option<MangaOcr>,
+) -> Result<String, Box<dyn Error>> {
     tracing::info!("Attempting to run OCR mode.");
 
     let sentence = if ocr_model == 0 {
@@ -312,7 +826,7 @@ pub fn ocr(
         String::new()
     };
 
-    run(&sentence, config)
+    Ok(sentence)
 
     /*
     let image = image.to_rgb8();
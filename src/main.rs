@@ -7,8 +7,13 @@
 use windows_sys::Win32::System::Console::AttachConsole;
 
 use clap::Parser;
+use clap::builder::{PossibleValue, PossibleValuesParser};
 use image::DynamicImage;
 use image::ImageReader;
+use popup_dictionary::config_file::{self, ConfigFile};
+use popup_dictionary::output::OutputMode;
+use popup_dictionary::plugin::PluginRegistry;
+use popup_dictionary::watch_filter::WatchFilters;
 use std::io::Cursor;
 use std::io::Read;
 use std::path::PathBuf;
@@ -56,6 +61,11 @@ struct Modes {
     #[arg(short = 'w', long = "watch")]
     watch: bool,
 
+    /// Watch primary clipboard/selection for newly selected text
+    #[arg(long = "watch-primary")]
+    #[cfg(target_os = "linux")]
+    watch_primary: bool,
+
     /// Use OCR mode. Reads image from path if provided, otherwise takes image data from stdin
     #[arg(short = 'o', long = "ocr", value_name = "PATH")]
     ocr: Option<Option<PathBuf>>,
@@ -64,22 +74,38 @@ struct Modes {
 #[derive(clap::Args, Debug)]
 #[group(required = false, multiple = true)]
 struct Options {
-    /// Initial plugin to load. Available: "kihon", "jotoba"
-    #[arg(long = "initial-plugin", value_name = "PLUGIN", help_heading = None)]
+    /// Initial plugin to load. Default: the first available plugin
+    #[arg(long = "initial-plugin", value_name = "PLUGIN", value_parser = plugin_names(), help_heading = None)]
     initial_plugin: Option<String>,
 
     /// Which engine to use for OCR. Available: "tesseract", "manga-ocr". Default: "tesseract"
-    #[arg(long = "ocr-engine", value_name = "ENGINE", help_heading = None)]
+    #[arg(long = "ocr-engine", value_name = "ENGINE", value_parser = PossibleValuesParser::new(config_file::OCR_ENGINES), help_heading = None)]
     ocr_engine: Option<String>,
 
     /// Try to open the window at the mouse cursor. Unlikely to work on wayland
-    #[arg(short = 'm', long = "at-mouse", help_heading = None)]
+    #[arg(short = 'm', long = "at-mouse", overrides_with = "no_open_at_cursor", help_heading = None)]
     open_at_cursor: bool,
 
+    /// Don't open the window at the mouse cursor, even if set in the config file
+    #[arg(long = "no-at-mouse", overrides_with = "open_at_cursor", help_heading = None)]
+    no_open_at_cursor: bool,
+
     /// Display input text in a text-box instead of in one line
-    #[arg(short = 'f', long = "full-text", help_heading = None)]
+    #[arg(short = 'f', long = "full-text", overrides_with = "no_wrapped", help_heading = None)]
     wrapped: bool,
 
+    /// Display input text in one line, even if --full-text is set in the config file
+    #[arg(long = "no-full-text", overrides_with = "wrapped", help_heading = None)]
+    no_wrapped: bool,
+
+    /// Show the reading above words with kanji in the input text
+    #[arg(long = "furigana", overrides_with = "no_furigana", help_heading = None)]
+    furigana: bool,
+
+    /// Don't show furigana, even if set in the config file
+    #[arg(long = "no-furigana", overrides_with = "furigana", help_heading = None)]
+    no_furigana: bool,
+
     /// Initial window width in pixels. Default: 450
     #[arg(long = "width", value_name = "PIXELS", help_heading = None)]
     initial_width: Option<u16>,
@@ -88,10 +114,54 @@ struct Options {
     #[arg(long = "height", value_name = "PIXELS", help_heading = None)]
     initial_height: Option<u16>,
 
+    /// In watch mode, collect newly copied text in a list in the popup instead of replacing the shown text
+    #[arg(long = "queue", overrides_with = "no_queue", help_heading = None)]
+    queue: bool,
+
+    /// In watch mode, replace the shown text, even if the queue is enabled in the config file
+    #[arg(long = "no-queue", overrides_with = "queue", help_heading = None)]
+    no_queue: bool,
+
+    /// In watch modes, ignore text with fewer characters than this
+    #[arg(long = "min-length", value_name = "CHARS", help_heading = None)]
+    min_length: Option<usize>,
+
+    /// In watch modes, ignore text with more characters than this
+    #[arg(long = "max-length", value_name = "CHARS", help_heading = None)]
+    max_length: Option<usize>,
+
+    /// In watch modes, ignore text matching this regular expression. Can be given multiple times
+    #[arg(long = "ignore", value_name = "REGEX", help_heading = None)]
+    ignore_patterns: Vec<String>,
+
+    /// In watch modes, wait until the content stopped changing for this long before looking it up. Default: 0 for --watch, 500 for --watch-primary
+    #[arg(long = "debounce", value_name = "MILLISECONDS", help_heading = None)]
+    debounce_ms: Option<u64>,
+
+    /// In watch mode, ignore images narrower or lower than this
+    #[arg(long = "min-image-size", value_name = "PIXELS", help_heading = None)]
+    min_image_size: Option<u32>,
+
+    /// In watch modes, only look up content from windows whose class contains this name (x11). Can be given multiple times
+    #[arg(long = "allow-source", value_name = "CLASS", help_heading = None)]
+    allowed_sources: Vec<String>,
+
+    /// Print tokens and definitions to stdout as plain text instead of opening a window
+    #[arg(long = "print", conflicts_with = "json", help_heading = None)]
+    print: bool,
+
+    /// Print tokens and definitions to stdout as JSON instead of opening a window
+    #[arg(long = "json", help_heading = None)]
+    json: bool,
+
     /// Show a tray icon
-    #[arg(long = "tray", help_heading = None)]
+    #[arg(long = "tray", overrides_with = "no_show_tray_icon", help_heading = None)]
     show_tray_icon: bool,
 
+    /// Don't show a tray icon, even if set in the config file
+    #[arg(long = "no-tray", overrides_with = "show_tray_icon", help_heading = None)]
+    no_show_tray_icon: bool,
+
     /// Enable verbose logging to terminal/console
     #[arg(long = "verbose", help_heading = None)]
     verbose: bool,
@@ -111,6 +181,15 @@ struct Options {
     font: Option<String>,
 }
 
+fn plugin_names() -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        PluginRegistry::global()
+            .all()
+            .iter()
+            .map(|plugin| PossibleValue::new(plugin.name).help(plugin.description)),
+    )
+}
+
 #[cfg(target_os = "windows")]
 const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
 
@@ -125,6 +204,15 @@ fn main() -> ExitCode {
         }
     }
 
+    // The plugin order has to be known before parsing, as the plugin names are offered as
+    // possible values
+    let file_config: Result<ConfigFile, Box<dyn std::error::Error>> =
+        config_file::load().and_then(|file_config| {
+            PluginRegistry::init(&file_config.plugins.order)?;
+            file_config.validate()?;
+            Ok(file_config)
+        });
+
     let cli: Args = Args::parse();
 
     init_logging(cli.options.verbose, cli.options.log_file);
@@ -134,62 +222,158 @@ fn main() -> ExitCode {
         env!("CARGO_PKG_VERSION")
     );
 
+    let file_config: ConfigFile = match file_config {
+        Ok(file_config) => file_config,
+        Err(e) => {
+            tracing::error!("Invalid config file: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(path) = config_file::path().filter(|path| path.exists()) {
+        tracing::info!("Loaded config file {}.", path.display());
+    }
+
     let config: popup_dictionary::app::Config = popup_dictionary::app::Config {
-        initial_plugin: cli.options.initial_plugin,
-        open_at_cursor: cli.options.open_at_cursor,
-        wrapped: cli.options.wrapped,
-        initial_width: cli.options.initial_width.unwrap_or(450),
-        initial_height: cli.options.initial_height.unwrap_or(450),
-        show_tray_icon: cli.options.show_tray_icon,
-        font: cli.options.font.unwrap_or(String::from("Noto Sans CJK JP")),
+        initial_plugin: cli.options.initial_plugin.or(file_config.plugins.initial),
+        open_at_cursor: cli_flag_or_file(
+            cli.options.open_at_cursor,
+            cli.options.no_open_at_cursor,
+            file_config.window.at_mouse,
+        ),
+        wrapped: cli_flag_or_file(
+            cli.options.wrapped,
+            cli.options.no_wrapped,
+            file_config.window.full_text,
+        ),
+        furigana: cli_flag_or_file(
+            cli.options.furigana,
+            cli.options.no_furigana,
+            file_config.window.furigana,
+        ),
+        initial_width: cli
+            .options
+            .initial_width
+            .or(file_config.window.width)
+            .unwrap_or(450),
+        initial_height: cli
+            .options
+            .initial_height
+            .or(file_config.window.height)
+            .unwrap_or(450),
+        show_tray_icon: cli_flag_or_file(
+            cli.options.show_tray_icon,
+            cli.options.no_show_tray_icon,
+            file_config.window.tray,
+        ),
+        font: cli
+            .options
+            .font
+            .or(file_config.window.font)
+            .unwrap_or(String::from("Noto Sans CJK JP")),
+        text_scale: file_config.window.text_scale.unwrap_or(1.0),
+        theme: file_config.theme,
+        remember_corrections: file_config.tokens.remember_corrections,
+        queue: cli_flag_or_file(
+            cli.options.queue,
+            cli.options.no_queue,
+            file_config.watch.queue,
+        ),
+        filters: WatchFilters {
+            min_length: cli.options.min_length.or(file_config.watch.min_length),
+            max_length: cli.options.max_length.or(file_config.watch.max_length),
+            ignore_patterns: cli_or_file(cli.options.ignore_patterns, file_config.watch.ignore),
+            debounce_ms: cli.options.debounce_ms.or(file_config.watch.debounce_ms),
+            min_image_size: cli
+                .options
+                .min_image_size
+                .or(file_config.watch.min_image_size),
+            allowed_sources: cli_or_file(
+                cli.options.allowed_sources,
+                file_config.watch.allow_sources,
+            ),
+        },
+        output: if cli.options.json {
+            OutputMode::Json
+        } else if cli.options.print {
+            OutputMode::Text
+        } else {
+            OutputMode::Window
+        },
     };
 
-    let mut initial_ocr_model: usize = 0;
-    if let Some(ocr_engine) = cli.options.ocr_engine {
-        if ocr_engine == "tesseract" {
-            initial_ocr_model = 0;
-        } else if ocr_engine == "manga-ocr" {
-            initial_ocr_model = 1;
-        }
-    }
+    let initial_ocr_model: usize = cli
+        .options
+        .ocr_engine
+        .as_deref()
+        .or(file_config.ocr.engine.as_deref())
+        .and_then(|engine| {
+            config_file::OCR_ENGINES
+                .iter()
+                .position(|name| *name == engine)
+        })
+        .unwrap_or(0);
+
+    let initial_plugin: usize = config
+        .initial_plugin
+        .as_ref()
+        .and_then(|name| PluginRegistry::global().position(name))
+        .unwrap_or(0);
 
     let paused = Arc::new(AtomicBool::new(false));
     let ocr_model = Arc::new(AtomicUsize::new(initial_ocr_model)); // 0=Tesseract; 1=MangaOCR
+    let active_plugin = Arc::new(AtomicUsize::new(initial_plugin)); // index into PluginRegistry
     let mut manga_ocr = None;
 
     #[cfg(target_os = "linux")]
     {
         if config.show_tray_icon {
-            crate::tray::spawn_tray_icon(Arc::clone(&paused), Arc::clone(&ocr_model));
+            crate::tray::spawn_tray_icon(
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            );
         }
 
         if let Some(text) = &cli.modes.text {
-            if let Err(e) = popup_dictionary::run(&text, config) {
+            if let Err(e) = popup_dictionary::run(text, config, initial_ocr_model) {
                 tracing::error!("Failed while running text mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
         } else if cli.modes.primary {
-            if let Err(e) = popup_dictionary::primary(config) {
+            if let Err(e) = popup_dictionary::primary(config, initial_ocr_model) {
                 tracing::error!("Failed while running primary mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
         } else if cli.modes.secondary {
-            if let Err(e) = popup_dictionary::secondary(config) {
+            if let Err(e) = popup_dictionary::secondary(config, initial_ocr_model) {
                 tracing::error!("Failed while running secondary mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
         } else if cli.modes.clipboard {
-            if let Err(e) = popup_dictionary::clipboard(config) {
+            if let Err(e) = popup_dictionary::clipboard(config, initial_ocr_model) {
                 tracing::error!("Failed while running clipboard mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
         } else if cli.modes.watch {
-            if let Err(e) =
-                popup_dictionary::watch(config, Arc::clone(&paused), Arc::clone(&ocr_model))
-            {
+            if let Err(e) = popup_dictionary::watch(
+                config,
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            ) {
                 tracing::error!("Failed while running watch mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
+        } else if cli.modes.watch_primary {
+            if let Err(e) = popup_dictionary::watch_primary(
+                config,
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            ) {
+                tracing::error!("Failed while running primary watch mode due to error: {e}");
+                return ExitCode::FAILURE;
+            }
         } else if let Some(ocr_path) = cli.modes.ocr {
             match get_image_for_ocr(ocr_path) {
                 Ok(image) => {
@@ -211,11 +395,18 @@ fn main() -> ExitCode {
             tracing::info!("No mode specified. Defaulting to watch mode with tray icon.");
             // Default to watch mode with tray icon if no mode set
             if !config.show_tray_icon {
-                crate::tray::spawn_tray_icon(Arc::clone(&paused), Arc::clone(&ocr_model));
+                crate::tray::spawn_tray_icon(
+                    Arc::clone(&paused),
+                    Arc::clone(&ocr_model),
+                    Arc::clone(&active_plugin),
+                );
             }
-            if let Err(e) =
-                popup_dictionary::watch(config, Arc::clone(&paused), Arc::clone(&ocr_model))
-            {
+            if let Err(e) = popup_dictionary::watch(
+                config,
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            ) {
                 tracing::error!("Failed while running watch mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
@@ -224,23 +415,30 @@ fn main() -> ExitCode {
     #[cfg(target_os = "windows")]
     {
         if config.show_tray_icon {
-            crate::tray::spawn_tray_icon(Arc::clone(&paused), Arc::clone(&ocr_model));
+            crate::tray::spawn_tray_icon(
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            );
         }
 
         if let Some(text) = &cli.modes.text {
-            if let Err(e) = popup_dictionary::run(&text, config) {
+            if let Err(e) = popup_dictionary::run(text, config, initial_ocr_model) {
                 tracing::error!("Failed while running text mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
         } else if cli.modes.clipboard {
-            if let Err(e) = popup_dictionary::clipboard(config) {
+            if let Err(e) = popup_dictionary::clipboard(config, initial_ocr_model) {
                 tracing::error!("Failed while running clipboard mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
         } else if cli.modes.watch {
-            if let Err(e) =
-                popup_dictionary::watch(config, Arc::clone(&paused), Arc::clone(&ocr_model))
-            {
+            if let Err(e) = popup_dictionary::watch(
+                config,
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            ) {
                 tracing::error!("Failed while running watch mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
@@ -265,11 +463,18 @@ fn main() -> ExitCode {
             tracing::info!("No mode specified. Defaulting to watch mode with tray icon.");
             // Default to watch mode with tray icon if no mode set
             if !config.show_tray_icon {
-                crate::tray::spawn_tray_icon(Arc::clone(&paused), Arc::clone(&ocr_model));
+                crate::tray::spawn_tray_icon(
+                    Arc::clone(&paused),
+                    Arc::clone(&ocr_model),
+                    Arc::clone(&active_plugin),
+                );
             }
-            if let Err(e) =
-                popup_dictionary::watch(config, Arc::clone(&paused), Arc::clone(&ocr_model))
-            {
+            if let Err(e) = popup_dictionary::watch(
+                config,
+                Arc::clone(&paused),
+                Arc::clone(&ocr_model),
+                Arc::clone(&active_plugin),
+            ) {
                 tracing::error!("Failed while running watch mode due to error: {e}");
                 return ExitCode::FAILURE;
             }
@@ -278,6 +483,24 @@ fn main() -> ExitCode {
     ExitCode::SUCCESS
 }
 
+/// Lists given on the command line replace the ones from the config file instead of extending
+/// them, so single entries can be overridden.
+fn cli_or_file(cli: Vec<String>, file: Vec<String>) -> Vec<String> {
+    if cli.is_empty() { file } else { cli }
+}
+
+/// Flags given on the command line, either as `--flag` or `--no-flag`, take precedence over the
+/// config file.
+fn cli_flag_or_file(enabled: bool, disabled: bool, file: bool) -> bool {
+    if enabled {
+        true
+    } else if disabled {
+        false
+    } else {
+        file
+    }
+}
+
 fn init_logging(verbose: bool, log_file: Option<Option<PathBuf>>) {
     let default_filter = if cfg!(debug_assertions) {
         "debug"
@@ -0,0 +1,461 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::Write;
+
+use crate::app;
+use crate::plugin::{
+    Kanji, LookupResult, Plugin, PluginDescriptor, PluginRegistry, Tag, Term, Token, Validity,
+};
+
+/// Where the result of a lookup goes.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OutputMode {
+    Window, // open the popup window
+    Text,   // print plain text to stdout
+    Json,   // print JSON to stdout
+}
+
+/*
+JSON schema printed by `--json`, one object per input text:
+{
+  "plugin": "kihon",
+  "sentence": "...",
+  "tokens": [
+    {
+      "surface": "...",                 // as written in the input text
+      "base": "...",                    // deinflected form
+      "conjugations": ["..."],          // raw conjugation forms from the tokenizer
+      "validity": "valid" | "invalid" | "unknown",
+      "forms": ["..."],                 // human readable conjugation forms
+      "terms": [
+        {
+          "term": "...",                // empty for kana-only terms
+          "reading": "...",
+          "furigana": [{ "ruby": "...", "rt": "..." | null }] | null,
+          "pitch_accents": [0],         // downstep after this mora of the reading, 0 for none
+          "senses": [
+            {
+              "tags": [{ "name": "...", "description": "..." }],
+              "glosses": ["..."],
+              "info": ["..."]
+            }
+          ],
+          "examples": [
+            {
+              "word": "...",            // the term as written in the sentence
+              "japanese": "...",
+              "english": "..."
+            }
+          ]
+        }
+      ],
+      "kanji": [
+        {
+          "character": "...",
+          "meanings": ["..."],
+          "onyomi": ["..."],
+          "kunyomi": ["..."],
+          "stroke_count": 0 | null,
+          "grade": 0 | null,            // 1-6 elementary school, 8 secondary school, 9-10 names
+          "jlpt": 0 | null,             // old, pre-2010 JLPT level
+          "frequency": 0 | null         // rank among the most used kanji
+        }
+      ],
+      "error": "..." | null             // set if the lookup failed
+    }
+  ]
+}
+*/
+#[derive(Serialize)]
+struct SentenceOutput<'a> {
+    plugin: &'a str,
+    sentence: &'a str,
+    tokens: Vec<TokenOutput<'a>>,
+}
+
+#[derive(Serialize)]
+struct TokenOutput<'a> {
+    surface: &'a str,
+    base: &'a str,
+    conjugations: &'a [String],
+    validity: &'a Validity,
+    forms: Vec<String>,
+    terms: Vec<Term>,
+    kanji: Vec<Kanji>,
+    error: Option<String>,
+}
+
+pub fn print_lookup(sentence: &str, config: &app::Config) -> Result<(), Box<dyn Error>> {
+    let mut stdout = std::io::stdout().lock();
+    write_lookup(&mut stdout, sentence, config)?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+pub fn write_lookup(
+    writer: &mut dyn Write,
+    sentence: &str,
+    config: &app::Config,
+) -> Result<(), Box<dyn Error>> {
+    let registry: &PluginRegistry = PluginRegistry::global();
+    let descriptor: &PluginDescriptor = match &config.initial_plugin {
+        Some(name) => registry
+            .get(name)
+            .ok_or_else(|| format!("Unknown plugin: {name}"))?,
+        None => registry.all().first().ok_or("No plugins available.")?,
+    };
+
+    tracing::info!("Running plugin {} without a window.", descriptor.name);
+    let plugin: Box<dyn Plugin> = descriptor.generate(sentence)?;
+
+    let tokens: Vec<TokenOutput> = plugin
+        .get_tokens()
+        .iter()
+        .map(|token| build_token_output(plugin.as_ref(), token))
+        .collect();
+    let output = SentenceOutput {
+        plugin: descriptor.name,
+        sentence,
+        tokens,
+    };
+
+    match config.output {
+        OutputMode::Json => {
+            serde_json::to_writer(&mut *writer, &output)?;
+            writeln!(writer)?;
+        }
+        _ => write_text(writer, &output)?,
+    }
+
+    Ok(())
+}
+
+fn build_token_output<'a>(plugin: &dyn Plugin, token: &'a Token) -> TokenOutput<'a> {
+    let (lookup_result, error): (LookupResult, Option<String>) = if token.is_valid() {
+        match plugin.lookup(token) {
+            Ok(lookup_result) => (lookup_result, None),
+            Err(e) => {
+                tracing::debug!(
+                    "Could not look up token {} due to error: {e}",
+                    token.input_word
+                );
+                (LookupResult::default(), Some(e.to_string()))
+            }
+        }
+    } else {
+        (LookupResult::default(), None)
+    };
+
+    TokenOutput {
+        surface: &token.input_word,
+        base: &token.deinflected_word,
+        conjugations: &token.conjugations,
+        validity: &token.validity,
+        forms: lookup_result.forms,
+        terms: lookup_result.terms,
+        kanji: lookup_result.kanji,
+        error,
+    }
+}
+
+/*
+Plain text format printed by `--print`:
+食べた (食べる) [Past]
+  食べる【たべる】
+    [ichidan, transitive]
+    1. to eat
+*/
+fn write_text(writer: &mut dyn Write, output: &SentenceOutput) -> std::io::Result<()> {
+    for token in &output.tokens {
+        // Skip punctuation and other tokens that can't be looked up
+        if matches!(token.validity, Validity::INVALID) {
+            continue;
+        }
+
+        write!(writer, "{}", token.surface)?;
+        if token.base != token.surface {
+            write!(writer, " ({})", token.base)?;
+        }
+        if !token.forms.is_empty() {
+            write!(writer, " [{}]", token.forms.join(", "))?;
+        }
+        writeln!(writer)?;
+
+        for term in &token.terms {
+            if term.term.is_empty() {
+                writeln!(writer, "  {}", term.reading)?;
+            } else {
+                writeln!(writer, "  {}【{}】", term.term, term.reading)?;
+            }
+
+            let mut last_tags: Option<&Vec<Tag>> = None;
+            for (idx, sense) in term.senses.iter().enumerate() {
+                if last_tags != Some(&sense.tags) && !sense.tags.is_empty() {
+                    let tag_names: Vec<&str> =
+                        sense.tags.iter().map(|tag| tag.name.as_str()).collect();
+                    writeln!(writer, "    [{}]", tag_names.join(", "))?;
+                }
+                last_tags = Some(&sense.tags);
+
+                writeln!(writer, "    {}. {}", idx + 1, sense.glosses.join(", "))?;
+                if !sense.info.is_empty() {
+                    writeln!(writer, "       {}", sense.info.join("; "))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{Example, Furigana, Sense};
+    use serde_json::{Value, json};
+
+    fn term() -> Term {
+        Term {
+            term: "食べる".to_string(),
+            reading: "たべる".to_string(),
+            furigana: Some(vec![
+                Furigana {
+                    ruby: "食".to_string(),
+                    rt: Some("た".to_string()),
+                },
+                Furigana {
+                    ruby: "べる".to_string(),
+                    rt: None,
+                },
+            ]),
+            pitch_accents: vec![0, 2],
+            senses: vec![
+                Sense {
+                    tags: vec![Tag {
+                        name: "ichidan".to_string(),
+                        description: "Ichidan verb".to_string(),
+                    }],
+                    glosses: vec!["to eat".to_string()],
+                    info: Vec::new(),
+                },
+                Sense {
+                    tags: vec![Tag {
+                        name: "ichidan".to_string(),
+                        description: "Ichidan verb".to_string(),
+                    }],
+                    glosses: vec!["to live on".to_string(), "to subsist on".to_string()],
+                    info: vec!["figuratively".to_string()],
+                },
+            ],
+            examples: vec![Example {
+                word: "食べた".to_string(),
+                japanese: "もう食べた。".to_string(),
+                english: "I already ate.".to_string(),
+            }],
+        }
+    }
+
+    fn token_output<'a>(
+        surface: &'a str,
+        base: &'a str,
+        conjugations: &'a [String],
+        validity: &'a Validity,
+    ) -> TokenOutput<'a> {
+        TokenOutput {
+            surface,
+            base,
+            conjugations,
+            validity,
+            forms: Vec::new(),
+            terms: Vec::new(),
+            kanji: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .expect("expected a JSON object")
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn json_schema() {
+        let conjugations: Vec<String> = vec!["連用形".to_string()];
+        let mut token: TokenOutput =
+            token_output("食べた", "食べる", &conjugations, &Validity::VALID);
+        token.forms = vec!["Past".to_string()];
+        token.terms = vec![term()];
+        let output = SentenceOutput {
+            plugin: "kihon",
+            sentence: "食べた",
+            tokens: vec![token],
+        };
+
+        let value: Value = serde_json::to_value(&output).unwrap();
+        assert_eq!(keys(&value), ["plugin", "sentence", "tokens"]);
+        assert_eq!(value["plugin"], "kihon");
+        assert_eq!(value["sentence"], "食べた");
+
+        let token: &Value = &value["tokens"][0];
+        assert_eq!(
+            keys(token),
+            [
+                "base",
+                "conjugations",
+                "error",
+                "forms",
+                "kanji",
+                "surface",
+                "terms",
+                "validity"
+            ]
+        );
+        assert_eq!(token["surface"], "食べた");
+        assert_eq!(token["base"], "食べる");
+        assert_eq!(token["conjugations"], json!(["連用形"]));
+        assert_eq!(token["validity"], "valid");
+        assert_eq!(token["forms"], json!(["Past"]));
+        assert_eq!(token["error"], Value::Null);
+
+        let term: &Value = &token["terms"][0];
+        assert_eq!(
+            keys(term),
+            [
+                "examples",
+                "furigana",
+                "pitch_accents",
+                "reading",
+                "senses",
+                "term"
+            ]
+        );
+        assert_eq!(term["term"], "食べる");
+        assert_eq!(term["reading"], "たべる");
+        assert_eq!(term["pitch_accents"], json!([0, 2]));
+        assert_eq!(
+            term["furigana"],
+            json!([{ "ruby": "食", "rt": "た" }, { "ruby": "べる", "rt": null }])
+        );
+        assert_eq!(
+            term["examples"],
+            json!([{
+                "word": "食べた",
+                "japanese": "もう食べた。",
+                "english": "I already ate."
+            }])
+        );
+        assert_eq!(
+            term["senses"][1],
+            json!({
+                "tags": [{ "name": "ichidan", "description": "Ichidan verb" }],
+                "glosses": ["to live on", "to subsist on"],
+                "info": ["figuratively"]
+            })
+        );
+    }
+
+    #[test]
+    fn json_null_furigana_and_error() {
+        let mut term: Term = term();
+        term.furigana = None;
+        let mut token: TokenOutput = token_output("たべる", "たべる", &[], &Validity::UNKNOWN);
+        token.terms = vec![term];
+        token.error = Some("Not found".to_string());
+
+        let value: Value = serde_json::to_value(&token).unwrap();
+        assert_eq!(value["terms"][0]["furigana"], Value::Null);
+        assert_eq!(value["error"], "Not found");
+    }
+
+    #[test]
+    fn json_validity_is_lowercase() {
+        for (validity, expected) in [
+            (Validity::VALID, "valid"),
+            (Validity::INVALID, "invalid"),
+            (Validity::UNKNOWN, "unknown"),
+        ] {
+            assert_eq!(serde_json::to_value(&validity).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn json_kanji() {
+        let mut token: TokenOutput = token_output("日", "日", &[], &Validity::VALID);
+        token.kanji = vec![Kanji {
+            character: "日".to_string(),
+            meanings: vec!["day".to_string(), "sun".to_string()],
+            onyomi: vec!["ニチ".to_string(), "ジツ".to_string()],
+            kunyomi: vec!["ひ".to_string(), "-び".to_string()],
+            stroke_count: Some(4),
+            grade: Some(1),
+            jlpt: None,
+            frequency: Some(1),
+        }];
+
+        let value: Value = serde_json::to_value(&token).unwrap();
+        assert_eq!(
+            value["kanji"],
+            json!([{
+                "character": "日",
+                "meanings": ["day", "sun"],
+                "onyomi": ["ニチ", "ジツ"],
+                "kunyomi": ["ひ", "-び"],
+                "stroke_count": 4,
+                "grade": 1,
+                "jlpt": null,
+                "frequency": 1
+            }])
+        );
+    }
+
+    #[test]
+    fn text_layout() {
+        let conjugations: Vec<String> = Vec::new();
+        let mut eaten: TokenOutput =
+            token_output("食べた", "食べる", &conjugations, &Validity::VALID);
+        eaten.forms = vec!["Past".to_string()];
+        eaten.terms = vec![term()];
+        let mut kana: TokenOutput = token_output("たべる", "たべる", &[], &Validity::UNKNOWN);
+        kana.terms = vec![Term {
+            term: String::new(),
+            furigana: None,
+            ..term()
+        }];
+        let output = SentenceOutput {
+            plugin: "kihon",
+            sentence: "食べた。たべる",
+            tokens: vec![
+                eaten,
+                token_output("。", "。", &[], &Validity::INVALID),
+                kana,
+            ],
+        };
+
+        let mut text: Vec<u8> = Vec::new();
+        write_text(&mut text, &output).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            concat!(
+                "食べた (食べる) [Past]\n",
+                "  食べる【たべる】\n",
+                "    [ichidan]\n",
+                "    1. to eat\n",
+                "    2. to live on, to subsist on\n",
+                "       figuratively\n",
+                "たべる\n",
+                "  たべる\n",
+                "    [ichidan]\n",
+                "    1. to eat\n",
+                "    2. to live on, to subsist on\n",
+                "       figuratively\n",
+            )
+        );
+    }
+}
@@ -1,60 +1,159 @@
-use egui::containers::Frame;
-use egui::{Context, Ui};
+use serde::Serialize;
+use std::error::Error;
+use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 
-use crate::app::MyApp;
-
 pub trait Plugin: Send + 'static {
-    fn load_plugin(sentence: &str) -> Self
+    fn load_plugin(sentence: &str) -> Result<Self, Box<dyn Error>>
     where
         Self: Sized;
     fn get_tokens(&self) -> &Vec<Token>;
-    fn display_token(&self, ctx: &Context, frame: &Frame, app: &MyApp, ui: &mut Ui, token: &Token);
-    fn open(&self, ctx: &Context);
+    /// Replaces the tokens, e.g. with ones the user corrected.
+    fn set_tokens(&mut self, tokens: Vec<Token>);
+    fn lookup(&self, token: &Token) -> Result<LookupResult, Box<dyn Error>>;
+    /// Looks up a word typed in by the user, independent of the tokens of the sentence.
+    fn search(&self, query: &str) -> Result<LookupResult, Box<dyn Error>>;
+    fn open_url(&self) -> Option<String>;
+    /// Furigana of a token known without looking it up, e.g. from tokenizing the sentence.
+    fn token_reading(&self, _token: &Token) -> Option<Vec<Furigana>> {
+        None
+    }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum Plugins {
-    Kihon,
-    Jotoba,
+/// What a plugin is able to do, used by the UI to decide what to offer for it.
+#[derive(Clone, Copy, Debug)]
+pub struct Capabilities {
+    pub offline: bool,    // works without an internet connection
+    pub web_search: bool, // `Plugin::open_url` is a web search instead of attributions
+}
+
+pub type PluginConstructor = fn(&str) -> Result<Box<dyn Plugin>, Box<dyn Error>>;
+pub type PluginPreload = fn() -> Result<(), Box<dyn Error>>;
+
+pub struct PluginDescriptor {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub capabilities: Capabilities,
+    constructor: PluginConstructor,
+    preload: Option<PluginPreload>,
 }
 
-impl Plugins {
-    pub fn all() -> Vec<Self> {
-        vec![Plugins::Kihon, Plugins::Jotoba]
+impl PluginDescriptor {
+    pub const fn new(
+        name: &'static str,
+        description: &'static str,
+        capabilities: Capabilities,
+        constructor: PluginConstructor,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            capabilities,
+            constructor,
+            preload: None,
+        }
+    }
+
+    /// Sets a hook that loads expensive resources ahead of time, so long running modes
+    /// like watch mode don't pay for them on the first popup.
+    pub const fn with_preload(mut self, preload: PluginPreload) -> Self {
+        self.preload = Some(preload);
+        self
     }
 
-    pub fn name(&self) -> &'static str {
-        match self {
-            Plugins::Kihon => "kihon",
-            Plugins::Jotoba => "jotoba",
+    pub fn preload(&self) -> Result<(), Box<dyn Error>> {
+        match self.preload {
+            Some(preload) => {
+                tracing::info!("Preloading plugin {}.", self.name);
+                preload()
+            }
+            None => Ok(()),
         }
     }
 
-    pub fn generate(&self, sentence: &str) -> Box<dyn Plugin> {
+    pub fn generate(&self, sentence: &str) -> Result<Box<dyn Plugin>, Box<dyn Error>> {
         let start: Instant = Instant::now();
 
-        let result: Box<dyn Plugin> = match self {
-            Plugins::Kihon => Box::new(
-                crate::plugins::kihon_plugin::kihon_plugin::KihonPlugin::load_plugin(sentence),
-            ),
-            Plugins::Jotoba => Box::new(
-                crate::plugins::jotoba_plugin::jotoba_plugin::JotobaPlugin::load_plugin(sentence),
-            ),
-        };
+        let result: Box<dyn Plugin> = (self.constructor)(sentence)?;
 
         let duration: Duration = start.elapsed();
         tracing::debug!(
-            "Plugin loaded in: {:.3} ms for sentence length {}",
+            "Plugin {} loaded in: {:.3} ms for sentence length {}",
+            self.name,
             duration.as_secs_f64() * 1000.0,
             sentence.len()
         );
 
-        result
+        Ok(result)
     }
 }
 
-#[derive(Clone, Debug)]
+static REGISTRY: OnceLock<PluginRegistry> = OnceLock::new();
+
+pub struct PluginRegistry {
+    plugins: Vec<PluginDescriptor>,
+}
+
+impl PluginRegistry {
+    /// Registry containing every plugin in `crate::plugins`, in the order they registered
+    /// unless `init` set another order. The first plugin is the default one.
+    pub fn global() -> &'static Self {
+        REGISTRY.get_or_init(Self::with_all_plugins)
+    }
+
+    /// Sets up the global registry with the given plugins moved to the front, in that order.
+    /// Has to be called before the registry is used for the first time.
+    pub fn init(order: &[String]) -> Result<&'static Self, Box<dyn Error>> {
+        let mut registry: PluginRegistry = Self::with_all_plugins();
+        for name in order.iter().rev() {
+            let position: usize = registry
+                .position(name)
+                .ok_or_else(|| format!("Unknown plugin {name} in plugin order."))?;
+            let descriptor: PluginDescriptor = registry.plugins.remove(position);
+            registry.plugins.insert(0, descriptor);
+        }
+
+        REGISTRY
+            .set(registry)
+            .map_err(|_| "The plugin registry is already in use.")?;
+        Ok(Self::global())
+    }
+
+    fn with_all_plugins() -> Self {
+        let mut registry: PluginRegistry = PluginRegistry {
+            plugins: Vec::new(),
+        };
+        crate::plugins::register_plugins(&mut registry);
+        registry
+    }
+
+    pub fn register(&mut self, descriptor: PluginDescriptor) {
+        if self.get(descriptor.name).is_some() {
+            tracing::warn!(
+                "A plugin named {} is already registered, ignoring duplicate.",
+                descriptor.name
+            );
+            return;
+        }
+        tracing::trace!("Registering plugin: {}.", descriptor.name);
+        self.plugins.push(descriptor);
+    }
+
+    pub fn all(&self) -> &[PluginDescriptor] {
+        &self.plugins
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PluginDescriptor> {
+        self.plugins.iter().find(|plugin| plugin.name == name)
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.plugins.iter().position(|plugin| plugin.name == name)
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Validity {
     VALID,
     INVALID,
@@ -78,3 +177,125 @@ impl Token {
         }
     }
 }
+
+/// Dictionary data found for a single token, independent of how it is displayed.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LookupResult {
+    pub forms: Vec<String>, // human readable conjugation forms of the token
+    pub terms: Vec<Term>,   // in the order they should be displayed
+    pub kanji: Vec<Kanji>,  // kanji of the token, in the order they are written
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Term {
+    pub term: String, // empty for kana-only terms
+    pub reading: String,
+    pub furigana: Option<Vec<Furigana>>,
+    pub pitch_accents: Vec<u32>, // downstep after this mora of the reading, 0 for none
+    pub senses: Vec<Sense>,
+    pub examples: Vec<Example>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Sense {
+    pub tags: Vec<Tag>,
+    pub glosses: Vec<String>,
+    pub info: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Example {
+    pub word: String, // the term as written in the sentence, e.g. to highlight it
+    pub japanese: String,
+    pub english: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Kanji {
+    pub character: String,
+    pub meanings: Vec<String>,
+    pub onyomi: Vec<String>,
+    pub kunyomi: Vec<String>,
+    pub stroke_count: Option<u8>,
+    pub grade: Option<u8>, // 1-6 elementary school, 8 secondary school, 9-10 jinmeiyou
+    pub jlpt: Option<u8>,  // level of the old, pre-2010 JLPT, 4 is the easiest
+    pub frequency: Option<u16>, // rank among the most used kanji in newspapers
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Furigana {
+    pub ruby: String,
+    pub rt: Option<String>,
+}
+
+impl Term {
+    /// The term as written, falling back to the reading for kana-only terms.
+    pub fn written_form(&self) -> &str {
+        if self.term.is_empty() {
+            &self.reading
+        } else {
+            &self.term
+        }
+    }
+
+    /// Furigana for `surface`, e.g. an inflected form of this term. Kanji keep their reading as
+    /// long as the surface is written like the term, the rest of the surface has none. None if
+    /// no kanji of the surface could be given a reading.
+    pub fn furigana_for(&self, surface: &str) -> Option<Vec<Furigana>> {
+        if self.term.is_empty() {
+            return None;
+        }
+        let term_furigana: Vec<Furigana> = self.furigana.clone().unwrap_or_else(|| {
+            vec![Furigana {
+                ruby: self.term.to_string(),
+                rt: Some(self.reading.to_string()),
+            }]
+        });
+
+        let mut furigana: Vec<Furigana> = Vec::new();
+        let mut rest: &str = surface;
+        for part in term_furigana {
+            let Some(remaining) = rest.strip_prefix(part.ruby.as_str()) else {
+                break;
+            };
+            rest = remaining;
+            furigana.push(part);
+        }
+        if !furigana.iter().any(|part| part.rt.is_some()) {
+            return None;
+        }
+        if !rest.is_empty() {
+            furigana.push(Furigana {
+                ruby: rest.to_string(),
+                rt: None,
+            });
+        }
+
+        Some(furigana)
+    }
+}
+
+pub fn contains_kanji(text: &str) -> bool {
+    text.chars().any(is_kanji)
+}
+
+pub fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' | '々')
+}
+
+/// Kanji of a text without repetitions, e.g. to look each one up.
+pub fn unique_kanji(text: &str) -> Vec<char> {
+    let mut kanji: Vec<char> = Vec::new();
+    for c in text.chars().filter(|c| is_kanji(*c) && *c != '々') {
+        if !kanji.contains(&c) {
+            kanji.push(c);
+        }
+    }
+    kanji
+}
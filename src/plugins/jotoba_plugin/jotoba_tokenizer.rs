@@ -14,6 +14,7 @@ use std::fmt;
 
 use crate::plugin::Token;
 use crate::plugin::Validity;
+use crate::plugin::contains_kanji;
 
 const JOTOBA_SUGGESTION_MAX: usize = 37;
 const COMMON_UNKNOWNS: phf::Map<&'static str, ()> = phf_map! {
@@ -227,20 +228,29 @@ impl Client {
 
 pub struct JotobaTokenizer {
     token_cache: Vec<CachedToken>,
+    readings: HashMap<String, Vec<Furigana>>, // by word, as found while tokenizing
     easy_client: Client,
 }
 
 impl JotobaTokenizer {
-    pub fn new() -> Self {
-        return Self {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
             token_cache: Vec::new(),
-            easy_client: Client::new().unwrap(), // TODO: handle error
-        };
+            readings: HashMap::new(),
+            easy_client: Client::new()?,
+        })
+    }
+
+    /// Furigana of a word of the last tokenized sentence, taken from the responses used for
+    /// tokenizing it, so they are known without looking the word up.
+    pub fn reading(&self, word: &str) -> Option<&[Furigana]> {
+        self.readings.get(word).map(Vec::as_slice)
     }
 
     pub fn tokenize(&mut self, sentence: &str) -> Result<Vec<Token>, Box<dyn Error>> {
         tracing::info!("Trying to tokenize with Jotoba.");
         tracing::debug!("Input text for Jotoba tokenization is: {}.", sentence);
+        self.readings.clear();
 
         // Firstly splitting the input sentence into valid and invalid sections of text
         let mut sentence_parts = Vec::new(); // (part, is_alphabetic)
@@ -295,6 +305,9 @@ impl JotobaTokenizer {
             // Get jotoba suggestions and build AhoCorasick left-match, do manual tokenization on non-matches
             let suggestion_response = self.query_suggestion(&part_slice)?;
             let suggestions = suggestion_response.suggestions;
+            for suggestion in &suggestions {
+                self.add_suggestion_reading(suggestion);
+            }
 
             let patterns: Vec<&str> = suggestions
                 .iter()
@@ -379,6 +392,10 @@ impl JotobaTokenizer {
                 for word in &response.words {
                     if let Some(kanji) = &word.reading.kanji {
                         if let Some(remainder) = sentence.strip_prefix(kanji) {
+                            if let Some(furigana) = &word.reading.furigana {
+                                self.readings
+                                    .insert(kanji.clone(), furigana.furigana.clone());
+                            }
                             let remainder_owned: String = remainder.to_string();
                             sentence.clear();
                             sentence.push_str(&remainder_owned);
@@ -521,16 +538,38 @@ impl JotobaTokenizer {
         Ok(tokens)
     }
 
+    /// Suggestions of words with kanji come with their kana reading, which is used for the
+    /// whole word.
+    fn add_suggestion_reading(&mut self, suggestion: &Suggestion) {
+        let Some(secondary) = &suggestion.secondary else {
+            return;
+        };
+        let (word, reading) = if contains_kanji(&suggestion.primary) {
+            (&suggestion.primary, secondary)
+        } else {
+            (secondary, &suggestion.primary)
+        };
+        if !contains_kanji(word) || contains_kanji(reading) {
+            return;
+        }
+
+        self.readings.entry(word.to_string()).or_insert_with(|| {
+            vec![Furigana {
+                ruby: word.to_string(),
+                rt: Some(reading.to_string()),
+            }]
+        });
+    }
+
     fn query_words(&mut self, sentence: &String) -> Result<WordsResponse, Box<dyn Error>> {
         tracing::trace!("Querying words for section: {}.", sentence);
 
         let easy: &mut Easy = &mut self.easy_client.words_easy;
 
         let mut buf: Vec<u8> = Vec::new();
-        let request_string: String = format!(
-            "{}{}{}",
-            r#"{"query":""#, sentence, r#"","language":"English"}"#
-        );
+        // Built with serde_json since searched text is typed in and may need escaping
+        let request_string: String =
+            serde_json::json!({ "query": sentence, "language": "English" }).to_string();
         let request: &[u8] = request_string.as_bytes();
         easy.post_fields_copy(request)?;
 
@@ -579,6 +618,12 @@ impl JotobaTokenizer {
         Ok(json)
     }
 
+    /// Looks up text typed in by the user. Unlike `get_response` it is not cached, since
+    /// searches are rarely repeated.
+    pub fn search(&mut self, query: &str) -> Result<WordsResponse, Box<dyn Error>> {
+        self.query_words(&query.to_string())
+    }
+
     pub fn get_response(&mut self, token: &Token) -> Result<WordsResponse, Box<dyn Error>> {
         tracing::trace!(
             "Retrieving token input: {}, deinflection: {}, conjugations: {}, is_valid: {}.",
@@ -1,2 +1,20 @@
+use crate::plugin::{Capabilities, Plugin, PluginDescriptor, PluginRegistry};
+
 pub mod jotoba_plugin;
 pub mod jotoba_tokenizer;
+
+pub fn register(registry: &mut PluginRegistry) {
+    registry.register(PluginDescriptor::new(
+        "jotoba",
+        "Online dictionary using the jotoba.de API",
+        Capabilities {
+            offline: false,
+            web_search: true,
+        },
+        |sentence| {
+            Ok(Box::new(jotoba_plugin::JotobaPlugin::load_plugin(
+                sentence,
+            )?))
+        },
+    ));
+}
@@ -8,7 +8,15 @@ use xz2::read::XzDecoder;
 
 const JUMANDIC_URL: &str =
     "https://github.com/daac-tools/vibrato/releases/download/v0.5.0/jumandic-mecab-7_0.tar.xz";
+// The JSON file inside each jmdict-simplified archive only carries the version, not the date
 const JMDICT_SIMPLIFIED_URL: &str = "https://github.com/scriptin/jmdict-simplified/releases/download/3.6.2%2B20260202123847/jmdict-eng-3.6.2+20260202123847.json.tgz";
+const JMDICT_SIMPLIFIED_FILE: &str = "jmdict-eng-3.6.2.json";
+const JMDICT_EXAMPLES_URL: &str = "https://github.com/scriptin/jmdict-simplified/releases/download/3.6.2%2B20260202123847/jmdict-examples-eng-3.6.2+20260202123847.json.tgz";
+const JMDICT_EXAMPLES_FILE: &str = "jmdict-examples-eng-3.6.2.json";
+const JMNEDICT_URL: &str = "https://github.com/scriptin/jmdict-simplified/releases/download/3.6.2%2B20260202123847/jmnedict-all-3.6.2+20260202123847.json.tgz";
+const JMNEDICT_FILE: &str = "jmnedict-all-3.6.2.json";
+const KANJIDIC2_URL: &str = "https://github.com/scriptin/jmdict-simplified/releases/download/3.6.2%2B20260202123847/kanjidic2-en-3.6.2+20260202123847.json.tgz";
+const KANJIDIC2_FILE: &str = "kanjidic2-en-3.6.2.json";
 const LEEDS_FREQUENCIES_URL: &str = "https://github.com/hingston/japanese/blob/78a5f64e872e4a2ad430adfd124c98f5f0a1619b/44492-japanese-words-latin-lines-removed.txt";
 const JMDICT_FURIGANA_URL: &str = "https://github.com/Doublevil/JmdictFurigana/releases/download/2.3.1%2B2026-01-25/JmdictFurigana.json";
 const MANGA_OCR_ENCODER_URL: &str =
@@ -46,28 +54,31 @@ pub fn fetch_jumandic(destination_path: &PathBuf) -> Result<(), Box<dyn Error>>
 }
 
 pub fn fetch_jmdict_simplified(destination_path: &PathBuf) -> Result<(), Box<dyn Error>> {
-    let response = reqwest::blocking::get(JMDICT_SIMPLIFIED_URL)?;
+    fetch_jmdict_simplified_archive(
+        destination_path,
+        JMDICT_SIMPLIFIED_URL,
+        JMDICT_SIMPLIFIED_FILE,
+    )?;
 
-    let gz_decoder = GzDecoder::new(response);
-    let mut archive = Archive::new(gz_decoder);
+    Ok(())
+}
 
-    for entry_result in archive.entries()? {
-        let mut entry = entry_result?;
-        let path = entry.path()?;
+pub fn fetch_jmdict_examples(destination_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    fetch_jmdict_simplified_archive(destination_path, JMDICT_EXAMPLES_URL, JMDICT_EXAMPLES_FILE)?;
 
-        if path.ends_with("jmdict-eng-3.6.2.json") {
-            if let Some(parent) = destination_path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
-            let mut out_file = File::create(destination_path)?;
+    Ok(())
+}
 
-            io::copy(&mut entry, &mut out_file)?;
+pub fn fetch_jmnedict(destination_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    fetch_jmdict_simplified_archive(destination_path, JMNEDICT_URL, JMNEDICT_FILE)?;
 
-            return Ok(());
-        }
-    }
+    Ok(())
+}
+
+pub fn fetch_kanjidic2(destination_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    fetch_jmdict_simplified_archive(destination_path, KANJIDIC2_URL, KANJIDIC2_FILE)?;
 
-    Err(Box::from("No JSON file found in .tgz archive"))
+    Ok(())
 }
 
 pub fn fetch_leeds_frequencies(destination_path: &PathBuf) -> Result<(), Box<dyn Error>> {
@@ -112,6 +123,15 @@ pub fn cleanup_files() {
 
         let jmdict_simplified_path = data_dir_path.clone().join("jmdict-simplified.json");
         try_remove_file(jmdict_simplified_path);
+
+        let kanjidic2_path = data_dir_path.clone().join("kanjidic2.json");
+        try_remove_file(kanjidic2_path);
+
+        let jmdict_examples_path = data_dir_path.clone().join("jmdict-examples.json");
+        try_remove_file(jmdict_examples_path);
+
+        let jmnedict_path = data_dir_path.clone().join("jmnedict.json");
+        try_remove_file(jmnedict_path);
     } else {
         tracing::warn!(
             "Could not cleanup files: No valid data path found in environment variables."
@@ -120,6 +140,10 @@ pub fn cleanup_files() {
 }
 
 fn try_remove_file(path: PathBuf) {
+    // Not every file is downloaded each time the database is populated
+    if !path.exists() {
+        return;
+    }
     if let Err(e) = std::fs::remove_file(&path) {
         tracing::warn!("Could not cleanup {} due to error: {e}", path.display());
     } else {
@@ -139,3 +163,32 @@ fn fetch_file(destination_path: &PathBuf, url: &str) -> Result<(), Box<dyn Error
 
     Ok(())
 }
+
+fn fetch_jmdict_simplified_archive(
+    destination_path: &PathBuf,
+    url: &str,
+    inner_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let response = reqwest::blocking::get(url)?;
+
+    let gz_decoder = GzDecoder::new(response);
+    let mut archive = Archive::new(gz_decoder);
+
+    for entry_result in archive.entries()? {
+        let mut entry = entry_result?;
+        let path = entry.path()?;
+
+        if path.ends_with(inner_name) {
+            if let Some(parent) = destination_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut out_file = File::create(destination_path)?;
+
+            io::copy(&mut entry, &mut out_file)?;
+
+            return Ok(());
+        }
+    }
+
+    Err(Box::from(format!("No {inner_name} found in .tgz archive")))
+}
@@ -1,14 +1,26 @@
-use serde::{Deserialize, Serialize};
-use sled::Db;
+use serde::de::{DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use sled::{Db, Tree};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::plugins::yomitan_plugin::yomitan_dictionary::read_pitch_accents;
+
+// How long a failed import of kanji, examples or names waits before it is tried again, so
+// starting without a network doesn't run into the download timeouts every time
+const OPTIONAL_IMPORT_RETRY: Duration = Duration::from_secs(24 * 60 * 60);
 
 #[derive(Clone)]
 pub struct Dictionary {
     db: Db,
+    names: Tree,         // JMnedict, only looked up when JMdict has no match
+    kanji: Tree,         // KANJIDIC2 by character
+    examples: Tree,      // example sentences by JMdict id
+    pitch_accents: Tree, // optional pitch accent dataset by term
 }
 
 #[derive(bincode::Encode, bincode::Decode, Debug)]
@@ -34,6 +46,31 @@ pub struct DictionaryMeaning {
     pub gloss: Vec<String>,
 }
 
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct DictionaryExample {
+    pub word: String, // the term as written in the sentence
+    pub japanese: String,
+    pub english: String,
+}
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct DictionaryPitch {
+    pub reading: String,
+    pub positions: Vec<u32>, // downstep after this mora, 0 for none
+}
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct DictionaryKanji {
+    pub literal: String,
+    pub meanings: Vec<String>,
+    pub onyomi: Vec<String>,
+    pub kunyomi: Vec<String>,
+    pub stroke_count: Option<u8>,
+    pub grade: Option<u8>,
+    pub jlpt: Option<u8>,
+    pub frequency: Option<u16>,
+}
+
 // JMDict json
 #[derive(Serialize, Deserialize)]
 struct JMDict {
@@ -80,6 +117,116 @@ struct Gloss {
 }
 // ---
 
+// jmdict-simplified examples json, only the parts with examples
+#[derive(Deserialize, Debug)]
+struct JMDictExamples {
+    words: Vec<ExampleWord>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExampleWord {
+    id: String,
+    sense: Vec<ExampleSense>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExampleSense {
+    #[serde(default)]
+    examples: Vec<ExampleEntry>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExampleEntry {
+    text: String, // the term as written in the sentence
+    sentences: Vec<ExampleSentence>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExampleSentence {
+    #[serde(alias = "lang")]
+    land: String, // the language, named like this in jmdict-simplified
+    text: String,
+}
+// ---
+
+// JMnedict json, read one word at a time since it is too big to hold at once
+#[derive(Deserialize, Debug)]
+struct NameWord {
+    id: String,
+    kanji: Vec<NameKanji>,
+    kana: Vec<NameKana>,
+    translation: Vec<NameTranslation>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NameKanji {
+    text: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NameKana {
+    text: String,
+    applies_to_kanji: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NameTranslation {
+    #[serde(rename = "type")]
+    name_types: Vec<String>,
+    translation: Vec<Gloss>,
+}
+// ---
+
+// KANJIDIC2 json
+#[derive(Deserialize, Debug)]
+struct KanjiDic2 {
+    characters: Vec<KanjiCharacter>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct KanjiCharacter {
+    literal: String,
+    misc: KanjiMisc,
+    reading_meaning: Option<KanjiReadingMeaning>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct KanjiMisc {
+    grade: Option<u8>,
+    stroke_counts: Vec<u8>, // the first one is the correct one, the rest common miscounts
+    frequency: Option<u16>,
+    jlpt_level: Option<u8>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KanjiReadingMeaning {
+    groups: Vec<KanjiGroup>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KanjiGroup {
+    readings: Vec<KanjiReading>,
+    meanings: Vec<KanjiMeaning>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KanjiReading {
+    #[serde(rename = "type")]
+    reading_type: String,
+    value: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct KanjiMeaning {
+    lang: String,
+    value: String,
+}
+// ---
+
 // jmdict-furigana json
 #[derive(Serialize, Deserialize, Debug)]
 struct JMDictFurigana {
@@ -98,6 +245,10 @@ pub struct Furigana {
 impl Dictionary {
     pub fn load_dictionary(path: &PathBuf) -> Result<Self, Box<dyn Error>> {
         let db: Db = sled::open(path)?;
+        let names: Tree = db.open_tree("jmnedict")?;
+        let kanji: Tree = db.open_tree("kanjidic2")?;
+        let examples: Tree = db.open_tree("examples")?;
+        let pitch_accents: Tree = db.open_tree("pitch_accents")?;
         if !db.was_recovered() {
             Self::populate_database(&db)?;
         } else {
@@ -106,7 +257,18 @@ impl Dictionary {
                 Self::populate_database(&db)?;
             }
         }
-        Ok(Self { db })
+        // Also adds kanji to databases populated before they were part of it. Words can still
+        // be looked up if these fail, so they are tried again on a later start instead.
+        Self::populate_optional(&kanji, "kanji", Self::populate_kanji)?;
+        Self::populate_optional(&examples, "example sentences", Self::populate_examples)?;
+        Self::populate_optional(&names, "names", Self::populate_names)?;
+        Ok(Self {
+            db,
+            names,
+            kanji,
+            examples,
+            pitch_accents,
+        })
     }
 
     fn populate_database(db: &Db) -> Result<&Db, Box<dyn Error>> {
@@ -119,6 +281,179 @@ impl Dictionary {
         Ok(db)
     }
 
+    /// Populates an optional tree unless it already is, or its last attempt failed less than
+    /// `OPTIONAL_IMPORT_RETRY` ago.
+    fn populate_optional(
+        tree: &Tree,
+        contents: &str,
+        populate: fn(&Tree) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        if tree.contains_key("successfully_populated_flag")? {
+            return Ok(());
+        }
+        let now: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let failed_at: Option<u64> = tree
+            .get("failed_at")?
+            .and_then(|failed_at| std::str::from_utf8(&failed_at).ok()?.parse().ok());
+        if let Some(failed_at) = failed_at
+            && now.saturating_sub(failed_at) < OPTIONAL_IMPORT_RETRY.as_secs()
+        {
+            tracing::info!("Not adding {contents} to the database yet, the last attempt failed.");
+            return Ok(());
+        }
+
+        if let Err(e) = populate(tree) {
+            tracing::warn!("Could not add {contents} to the database for Kihon plugin: {e}");
+            tree.insert("failed_at", now.to_string().as_bytes())?;
+            tree.flush()?;
+        }
+        Ok(())
+    }
+
+    fn populate_kanji(kanji: &Tree) -> Result<(), Box<dyn Error>> {
+        tracing::info!("Trying to add kanji to database for Kihon plugin.");
+
+        kanji.clear()?;
+        Self::parse_kanjidic2(kanji)?;
+        kanji.insert("successfully_populated_flag", "")?;
+        kanji.flush()?;
+        crate::plugins::kihon_plugin::dependencies::cleanup_files();
+        Ok(())
+    }
+
+    fn populate_examples(examples: &Tree) -> Result<(), Box<dyn Error>> {
+        tracing::info!("Trying to add example sentences to database for Kihon plugin.");
+
+        examples.clear()?;
+        Self::parse_jmdict_examples(examples)?;
+        examples.insert("successfully_populated_flag", "")?;
+        examples.flush()?;
+        crate::plugins::kihon_plugin::dependencies::cleanup_files();
+        Ok(())
+    }
+
+    fn populate_names(names: &Tree) -> Result<(), Box<dyn Error>> {
+        tracing::info!("Trying to add names to database for Kihon plugin.");
+
+        // Leftovers of an interrupted import would otherwise be added to
+        names.clear()?;
+        Self::parse_jmnedict(names)?;
+        names.insert("successfully_populated_flag", "")?;
+        names.flush()?;
+        crate::plugins::kihon_plugin::dependencies::cleanup_files();
+        Ok(())
+    }
+
+    /// Imports the optional pitch accent dataset, again whenever it changed. Without one,
+    /// previously imported pitch accents are removed.
+    pub fn import_pitch_accents(&self, path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+        let source: Option<String> = match path {
+            Some(path) => {
+                let metadata = std::fs::metadata(path)
+                    .map_err(|e| format!("Could not read {}: {e}", path.display()))?;
+                let modified: u64 = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+                Some(format!("{}:{}:{modified}", path.display(), metadata.len()))
+            }
+            None => None,
+        };
+        let imported_source: Option<sled::IVec> = self.pitch_accents.get("source")?;
+        if imported_source.as_deref() == source.as_ref().map(|source| source.as_bytes()) {
+            return Ok(());
+        }
+
+        self.pitch_accents.clear()?;
+        let mut batch: sled::Batch = sled::Batch::default();
+        if let (Some(path), Some(source)) = (path, &source) {
+            tracing::info!(
+                "Importing pitch accents from {} for Kihon plugin.",
+                path.display()
+            );
+            let is_zip: bool = path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+            let pitch_accents: HashMap<String, Vec<DictionaryPitch>> = if is_zip {
+                read_pitch_accents(path)?
+                    .into_iter()
+                    .map(|(term, pitches)| {
+                        let pitches: Vec<DictionaryPitch> = pitches
+                            .into_iter()
+                            .map(|pitch| DictionaryPitch {
+                                reading: pitch.reading,
+                                positions: pitch.positions,
+                            })
+                            .collect();
+                        (term, pitches)
+                    })
+                    .collect()
+            } else {
+                Self::parse_kanjium_accents(path)?
+            };
+
+            for (term, pitches) in &pitch_accents {
+                batch.insert(
+                    term.as_bytes(),
+                    bincode::encode_to_vec(pitches, bincode::config::standard())?,
+                );
+            }
+            batch.insert("source", source.as_bytes());
+            tracing::info!("Imported pitch accents of {} terms.", pitch_accents.len());
+        }
+        self.pitch_accents.apply_batch(batch)?;
+        self.pitch_accents.flush()?;
+
+        Ok(())
+    }
+
+    fn parse_kanjium_accents(
+        path: &Path,
+    ) -> Result<HashMap<String, Vec<DictionaryPitch>>, Box<dyn Error>> {
+        let mut pitch_accents: HashMap<String, Vec<DictionaryPitch>> = HashMap::new();
+        let file: File = File::open(path)?;
+
+        /*
+        One term per line, the reading is empty for kana-only terms:
+        食べる	たべる	2
+        ああ		1,0
+        Positions can be annotated with parts of speech, e.g. (名)0,(副)1
+        */
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            let mut fields = line.split('\t');
+            let (Some(term), Some(reading), Some(accents)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            let mut positions: Vec<u32> = Vec::new();
+            for accent in accents.split(',') {
+                let digits: String = accent.chars().filter(char::is_ascii_digit).collect();
+                if let Ok(position) = digits.parse()
+                    && !positions.contains(&position)
+                {
+                    positions.push(position);
+                }
+            }
+            if positions.is_empty() {
+                continue;
+            }
+
+            pitch_accents
+                .entry(term.to_string())
+                .or_default()
+                .push(DictionaryPitch {
+                    reading: if reading.is_empty() { term } else { reading }.to_string(),
+                    positions,
+                });
+        }
+
+        Ok(pitch_accents)
+    }
+
     const GENERIC_TAGS: phf::Map<&'static str, &'static str> = phf::phf_map! {
         "?" => "unclassified",
         "noun" => "noun (common) (futsuumeishi)",
@@ -158,6 +493,34 @@ impl Dictionary {
         "nidan" => "nidan verb (archaic)",
         "yodan" => "yodan verb (archaic)",
         "nari-adj" => "archaic/formal form of na-adjective",
+        // JMnedict name types
+        "surname" => "family or surname",
+        "place" => "place name",
+        "unclass" => "unclassified name",
+        "company" => "company name",
+        "product" => "product name",
+        "work" => "work of art, literature, music, etc. name",
+        "masc" => "male given name or forename",
+        "fem" => "female given name or forename",
+        "person" => "full name of a particular person",
+        "given" => "given name or forename, gender not specified",
+        "station" => "railway station",
+        "organization" => "organization name",
+        "ok" => "old or irregular kana form",
+        "char" => "character",
+        "creat" => "creature",
+        "dei" => "deity",
+        "doc" => "document",
+        "ev" => "event",
+        "fict" => "fiction",
+        "group" => "group",
+        "leg" => "legend",
+        "myth" => "mythology",
+        "obj" => "object",
+        "oth" => "other",
+        "relig" => "religion",
+        "serv" => "service",
+        "ship" => "ship name",
     };
 
     pub fn get_tag(tag: &str) -> &str {
@@ -308,6 +671,197 @@ impl Dictionary {
         Ok(())
     }
 
+    fn parse_jmdict_examples(examples_tree: &Tree) -> Result<(), Box<dyn Error>> {
+        let mut jmdict_examples_path: PathBuf = match dirs::data_dir() {
+            Some(path) => path,
+            None => Err("No valid data path found in environment variables.")?,
+        };
+        jmdict_examples_path = jmdict_examples_path
+            .join("popup_dictionary")
+            .join("dicts")
+            .join("jmdict-examples.json");
+        if !jmdict_examples_path
+            .try_exists()
+            .is_ok_and(|verified| verified)
+        {
+            crate::plugins::kihon_plugin::dependencies::fetch_jmdict_examples(
+                &jmdict_examples_path,
+            )?;
+        }
+        let file: File = File::open(jmdict_examples_path)?;
+        let jmdict_examples: JMDictExamples = serde_json::from_reader(BufReader::new(file))?;
+
+        // Indexed by the JMdict id, which every term of a word shares
+        let mut batch: sled::Batch = sled::Batch::default();
+        for word in jmdict_examples.words {
+            let mut examples: Vec<DictionaryExample> = Vec::new();
+            for example in word.sense.into_iter().flat_map(|sense| sense.examples) {
+                let sentence = |language: &str| -> Option<String> {
+                    example
+                        .sentences
+                        .iter()
+                        .find(|sentence| sentence.land == language)
+                        .map(|sentence| sentence.text.to_string())
+                };
+                let (Some(japanese), Some(english)) = (sentence("jpn"), sentence("eng")) else {
+                    continue;
+                };
+                if examples.iter().any(|other| other.japanese == japanese) {
+                    continue;
+                }
+                examples.push(DictionaryExample {
+                    word: example.text,
+                    japanese,
+                    english,
+                });
+            }
+
+            if !examples.is_empty() {
+                batch.insert(
+                    word.id.as_bytes(),
+                    bincode::encode_to_vec(&examples, bincode::config::standard())?,
+                );
+            }
+        }
+        examples_tree.apply_batch(batch)?;
+
+        Ok(())
+    }
+
+    fn parse_jmnedict(names: &Tree) -> Result<(), Box<dyn Error>> {
+        let mut jmnedict_path: PathBuf = match dirs::data_dir() {
+            Some(path) => path,
+            None => Err("No valid data path found in environment variables.")?,
+        };
+        jmnedict_path = jmnedict_path
+            .join("popup_dictionary")
+            .join("dicts")
+            .join("jmnedict.json");
+        if !jmnedict_path.try_exists().is_ok_and(|verified| verified) {
+            crate::plugins::kihon_plugin::dependencies::fetch_jmnedict(&jmnedict_path)?;
+        }
+        let file: File = File::open(jmnedict_path)?;
+
+        let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(file));
+        let name_count: usize = NamesSeed { names }.deserialize(&mut deserializer)?;
+        tracing::info!("Added {name_count} names to database for Kihon plugin.");
+
+        Ok(())
+    }
+
+    fn insert_name(names: &Tree, word: &NameWord) -> Result<(), Box<dyn Error>> {
+        let meanings: Vec<DictionaryMeaning> = word
+            .translation
+            .iter()
+            .map(|translation| DictionaryMeaning {
+                tags: translation.name_types.to_vec(),
+                info: Vec::new(),
+                gloss: translation
+                    .translation
+                    .iter()
+                    .map(|gloss| gloss.text.to_string())
+                    .collect(),
+            })
+            .collect();
+
+        let wildcard: String = String::from("*");
+        for kanji in &word.kanji {
+            for kana in word.kana.iter().filter(|kana| {
+                kana.applies_to_kanji.contains(&wildcard)
+                    || kana.applies_to_kanji.contains(&kanji.text)
+            }) {
+                for key in [
+                    format!("term:{}", kanji.text),
+                    format!("reading:{}", kana.text),
+                ] {
+                    Self::insert_entry(
+                        names,
+                        &key,
+                        &word.id,
+                        &None,
+                        &false,
+                        &kanji.text,
+                        &kana.text,
+                        &None,
+                        &meanings,
+                    )?;
+                }
+            }
+        }
+        if word.kanji.is_empty() {
+            for kana in &word.kana {
+                Self::insert_entry(
+                    names,
+                    &format!("reading:{}", kana.text),
+                    &word.id,
+                    &None,
+                    &false,
+                    "",
+                    &kana.text,
+                    &None,
+                    &meanings,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_kanjidic2(kanji: &Tree) -> Result<(), Box<dyn Error>> {
+        let mut kanjidic2_path: PathBuf = match dirs::data_dir() {
+            Some(path) => path,
+            None => Err("No valid data path found in environment variables.")?,
+        };
+        kanjidic2_path = kanjidic2_path
+            .join("popup_dictionary")
+            .join("dicts")
+            .join("kanjidic2.json");
+        if !kanjidic2_path.try_exists().is_ok_and(|verified| verified) {
+            crate::plugins::kihon_plugin::dependencies::fetch_kanjidic2(&kanjidic2_path)?;
+        }
+        let file: File = File::open(kanjidic2_path)?;
+        let kanjidic2: KanjiDic2 = serde_json::from_reader(BufReader::new(file))?;
+
+        let mut batch: sled::Batch = sled::Batch::default();
+        for character in kanjidic2.characters {
+            let groups: &[KanjiGroup] = match &character.reading_meaning {
+                Some(reading_meaning) => &reading_meaning.groups,
+                None => &[],
+            };
+            let readings = |reading_type: &str| -> Vec<String> {
+                groups
+                    .iter()
+                    .flat_map(|group| &group.readings)
+                    .filter(|reading| reading.reading_type == reading_type)
+                    .map(|reading| reading.value.to_string())
+                    .collect()
+            };
+
+            let dictionary_kanji: DictionaryKanji = DictionaryKanji {
+                literal: character.literal.to_string(),
+                meanings: groups
+                    .iter()
+                    .flat_map(|group| &group.meanings)
+                    .filter(|meaning| meaning.lang == "en")
+                    .map(|meaning| meaning.value.to_string())
+                    .collect(),
+                onyomi: readings("ja_on"),
+                kunyomi: readings("ja_kun"),
+                stroke_count: character.misc.stroke_counts.first().copied(),
+                grade: character.misc.grade,
+                jlpt: character.misc.jlpt_level,
+                frequency: character.misc.frequency,
+            };
+            batch.insert(
+                character.literal.as_bytes(),
+                bincode::encode_to_vec(&dictionary_kanji, bincode::config::standard())?,
+            );
+        }
+        kanji.apply_batch(batch)?;
+
+        Ok(())
+    }
+
     fn parse_leeds_frequencies() -> Result<HashMap<String, u32>, Box<dyn Error>> {
         let mut frequency_map: HashMap<String, u32> = HashMap::new();
         let mut leeds_frequency_path: PathBuf = match dirs::data_dir() {
@@ -423,7 +977,7 @@ impl Dictionary {
     }
 
     fn insert_entry(
-        db: &Db,
+        db: &Tree,
         key: &str,
         id: &str,
         frequency: &Option<&u32>,
@@ -566,6 +1120,60 @@ impl Dictionary {
         Ok(None)
     }
 
+    /// Looks a word up in JMnedict, with the same keys as JMdict.
+    pub fn lookup_name(&self, word: &str) -> Result<Option<DictionaryEntry>, Box<dyn Error>> {
+        for key in [format!("term:{word}"), format!("reading:{word}")] {
+            if let Some(serialized_entry) = self.names.get(key)? {
+                let (entry, _): (DictionaryEntry, usize) =
+                    bincode::decode_from_slice(&serialized_entry, bincode::config::standard())?;
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Example sentences of a word by its JMdict id.
+    pub fn lookup_examples(&self, id: &str) -> Result<Vec<DictionaryExample>, Box<dyn Error>> {
+        match self.examples.get(id)? {
+            Some(serialized_examples) => {
+                let (examples, _): (Vec<DictionaryExample>, usize) =
+                    bincode::decode_from_slice(&serialized_examples, bincode::config::standard())?;
+                Ok(examples)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Pitch accents of a term with this reading, empty if there are none.
+    pub fn lookup_pitch_accents(
+        &self,
+        term: &str,
+        reading: &str,
+    ) -> Result<Vec<u32>, Box<dyn Error>> {
+        let Some(serialized_pitches) = self.pitch_accents.get(term)? else {
+            return Ok(Vec::new());
+        };
+        let (pitches, _): (Vec<DictionaryPitch>, usize) =
+            bincode::decode_from_slice(&serialized_pitches, bincode::config::standard())?;
+
+        Ok(pitches
+            .into_iter()
+            .find(|pitch| pitch.reading == reading)
+            .map(|pitch| pitch.positions)
+            .unwrap_or_default())
+    }
+
+    pub fn lookup_kanji(&self, character: char) -> Result<Option<DictionaryKanji>, Box<dyn Error>> {
+        match self.kanji.get(character.to_string())? {
+            Some(serialized_kanji) => {
+                let (kanji, _): (DictionaryKanji, usize) =
+                    bincode::decode_from_slice(&serialized_kanji, bincode::config::standard())?;
+                Ok(Some(kanji))
+            }
+            None => Ok(None),
+        }
+    }
+
     const JMDICT_GENERIC_MAPPING: phf::Map<&'static str, &'static str> = phf::phf_map! {
         "unc" => "?",
         "n" => "noun",
@@ -651,3 +1259,65 @@ impl Dictionary {
         "v2w-s" => "nidan-l",
     };
 }
+
+/// Inserts the words of the JMnedict json while reading it.
+struct NamesSeed<'a> {
+    names: &'a Tree,
+}
+
+impl<'de> DeserializeSeed<'de> for NamesSeed<'_> {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NamesSeed<'_> {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a JMnedict object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<usize, A::Error> {
+        let mut name_count: usize = 0;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "words" {
+                name_count = map.next_value_seed(NameWordsSeed { names: self.names })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(name_count)
+    }
+}
+
+struct NameWordsSeed<'a> {
+    names: &'a Tree,
+}
+
+impl<'de> DeserializeSeed<'de> for NameWordsSeed<'_> {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<usize, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NameWordsSeed<'_> {
+    type Value = usize;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a list of JMnedict words")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<usize, A::Error> {
+        let mut name_count: usize = 0;
+        while let Some(word) = seq.next_element::<NameWord>()? {
+            Dictionary::insert_name(self.names, &word).map_err(A::Error::custom)?;
+            name_count += 1;
+        }
+        Ok(name_count)
+    }
+}
@@ -27,10 +27,7 @@ pub fn get_form(form: &str) -> &str {
     }
 }
 
-pub fn tokenize(
-    query: &String,
-    dictionary: &crate::plugins::kihon_plugin::jmdict_dictionary::Dictionary,
-) -> Result<Vec<Token>, Box<dyn Error>> {
+pub fn load_tokenizer() -> Result<Tokenizer, Box<dyn Error>> {
     let mut system_dic_path: PathBuf = match dirs::data_dir() {
         Some(path) => path,
         None => Err("No valid data path found in environment variables.")?,
@@ -49,7 +46,14 @@ pub fn tokenize(
     let reader: BufReader<File> = BufReader::new(system_dic);
     let dict: Dictionary = Dictionary::read(reader)?;
 
-    let tokenizer: Tokenizer = Tokenizer::new(dict);
+    Ok(Tokenizer::new(dict))
+}
+
+pub fn tokenize(
+    query: &String,
+    tokenizer: &Tokenizer,
+    dictionary: &crate::plugins::kihon_plugin::jmdict_dictionary::Dictionary,
+) -> Result<Vec<Token>, Box<dyn Error>> {
     let mut worker = tokenizer.new_worker();
 
     worker.reset_sentence(query);
@@ -85,7 +89,7 @@ pub fn tokenize(
         });
     }
 
-    words = improve_tokens(&mut words, dictionary);
+    words = improve_tokens(&mut words, dictionary)?;
 
     Ok(words)
 }
@@ -93,7 +97,7 @@ pub fn tokenize(
 fn improve_tokens(
     words: &mut Vec<Token>,
     dictionary: &crate::plugins::kihon_plugin::jmdict_dictionary::Dictionary,
-) -> Vec<Token> {
+) -> Result<Vec<Token>, Box<dyn Error>> {
     let mut new_words: Vec<Token> = Vec::new();
     let mut start_idx: usize = 0;
 
@@ -131,20 +135,9 @@ fn improve_tokens(
                     .collect::<String>();
                 only_last_base.push_str(&words[end_idx_minus_one].deinflected_word);
 
-                if let Some(_) = dictionary.lookup(&surface).expect(&format!(
-                    "Error getting from database when looking up base: {}",
-                    surface
-                )) {
-                    found_match = true;
-                } else if let Some(_) = dictionary.lookup(&base).expect(&format!(
-                    "Error getting from database when looking up base: {}",
-                    base
-                )) {
+                if dictionary.lookup(&surface)?.is_some() || dictionary.lookup(&base)?.is_some() {
                     found_match = true;
-                } else if let Some(_) = dictionary.lookup(&only_last_base).expect(&format!(
-                    "Error getting from database when looking up base: {}",
-                    only_last_base
-                )) {
+                } else if dictionary.lookup(&only_last_base)?.is_some() {
                     //println!("TRUE: {:?} {:? } {:?}", surface, base, only_last_base);
                     base = only_last_base;
                     found_match = true;
@@ -182,5 +175,5 @@ fn improve_tokens(
         }
     }
 
-    new_words
+    Ok(new_words)
 }
@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use vibrato::Tokenizer;
+
+use crate::config_file;
+use crate::plugins::kihon_plugin::jmdict_dictionary::Dictionary;
+use crate::plugins::kihon_plugin::jumandic_tokenizer::load_tokenizer;
+
+// Loaded once per process and shared by every popup, since opening the database and reading
+// the system dictionary take far longer than the lookups themselves.
+static ENGINE: Mutex<Option<Arc<KihonEngine>>> = Mutex::new(None);
+
+pub struct KihonEngine {
+    pub dictionary: Dictionary,
+    pub tokenizer: Tokenizer,
+}
+
+impl KihonEngine {
+    /// Returns the resident engine, loading it first if this is the first call.
+    /// Failed loads are not cached, so a later call tries again.
+    pub fn get() -> Result<Arc<Self>, Box<dyn Error>> {
+        // Holding the lock while loading makes concurrent callers wait for the same engine
+        // instead of opening the database twice.
+        let mut engine = ENGINE
+            .lock()
+            .map_err(|_| "Kihon engine lock is poisoned.")?;
+        if let Some(engine) = engine.as_ref() {
+            return Ok(Arc::clone(engine));
+        }
+
+        let start: Instant = Instant::now();
+        let loaded_engine: Arc<Self> = Arc::new(Self::load()?);
+        let duration: Duration = start.elapsed();
+        tracing::info!(
+            "Kihon engine loaded in: {:.3} ms",
+            duration.as_secs_f64() * 1000.0
+        );
+
+        *engine = Some(Arc::clone(&loaded_engine));
+        Ok(loaded_engine)
+    }
+
+    fn load() -> Result<Self, Box<dyn Error>> {
+        let db_path: PathBuf = match dirs::data_dir() {
+            Some(path) => path.join("popup_dictionary").join("db"),
+            None => {
+                return Err(Box::from(
+                    "No valid data path found in environment variables.",
+                ));
+            }
+        };
+
+        let dictionary: Dictionary = Dictionary::load_dictionary(&db_path)
+            .map_err(|e| format!("Could not load the Kihon database: {e}"))?;
+        // Optional, words are still shown without pitch accents if this fails
+        let pitch_accents_path: Option<PathBuf> = config_file::load()
+            .ok()
+            .and_then(|config| config.kihon.pitch_accents)
+            .map(|path| config_file::resolve_path(&path));
+        if let Err(e) = dictionary.import_pitch_accents(pitch_accents_path.as_deref()) {
+            tracing::warn!("Could not import pitch accents for the Kihon plugin: {e}");
+        }
+        let tokenizer: Tokenizer =
+            load_tokenizer().map_err(|e| format!("Could not load the tokenizer: {e}"))?;
+
+        Ok(Self {
+            dictionary,
+            tokenizer,
+        })
+    }
+}
@@ -1,4 +1,25 @@
+use crate::plugin::{Capabilities, Plugin, PluginDescriptor, PluginRegistry};
+
 pub mod dependencies;
 pub mod jmdict_dictionary;
 pub mod jumandic_tokenizer;
+pub mod kihon_engine;
 pub mod kihon_plugin;
+
+pub fn register(registry: &mut PluginRegistry) {
+    registry.register(
+        PluginDescriptor::new(
+            "kihon",
+            "Offline dictionary using JMdict and the Vibrato tokenizer",
+            Capabilities {
+                offline: true,
+                web_search: false,
+            },
+            |sentence| Ok(Box::new(kihon_plugin::KihonPlugin::load_plugin(sentence)?)),
+        )
+        .with_preload(|| {
+            kihon_engine::KihonEngine::get()?;
+            Ok(())
+        }),
+    );
+}
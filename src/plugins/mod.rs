@@ -1,2 +1,12 @@
+use crate::plugin::PluginRegistry;
+
 pub mod jotoba_plugin;
 pub mod kihon_plugin;
+pub mod yomitan_plugin;
+
+/// Registers all built-in plugins. The first one registered is the default plugin.
+pub fn register_plugins(registry: &mut PluginRegistry) {
+    kihon_plugin::register(registry);
+    jotoba_plugin::register(registry);
+    yomitan_plugin::register(registry);
+}
@@ -0,0 +1,30 @@
+use crate::plugin::{Capabilities, Plugin, PluginDescriptor, PluginRegistry};
+
+pub mod yomitan_dictionary;
+pub mod yomitan_engine;
+#[allow(clippy::module_inception)] // named like the other plugins
+pub mod yomitan_plugin;
+
+pub fn register(registry: &mut PluginRegistry) {
+    registry.register(
+        PluginDescriptor::new(
+            "yomitan",
+            "Offline dictionary using imported Yomitan dictionaries",
+            Capabilities {
+                offline: true,
+                web_search: false,
+            },
+            |sentence| {
+                Ok(Box::new(yomitan_plugin::YomitanPlugin::load_plugin(
+                    sentence,
+                )?))
+            },
+        )
+        .with_preload(|| {
+            yomitan_engine::YomitanEngine::get()?;
+            // Tokenizes with the Kihon engine
+            crate::plugins::kihon_plugin::kihon_engine::KihonEngine::get()?;
+            Ok(())
+        }),
+    );
+}
@@ -0,0 +1,595 @@
+use serde::Deserialize;
+use serde_json::Value;
+use sled::Db;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use zip::ZipArchive;
+
+/*
+Database layout, with one prefix per dictionary so several can be stacked and replaced on
+their own:
+imported:<title>            -- signature of the imported zip, to notice when it changed
+<title>\0term:<term>        -- terms written with kanji
+<title>\0reading:<reading>  -- terms by their kana reading
+<title>\0freq:<term>        -- frequency rank from term_meta_bank, lower is more frequent
+<title>\0pitch:<term>       -- pitch accents from term_meta_bank
+<title>\0kanji:<character>  -- kanji from kanji_bank
+*/
+const SEPARATOR: char = '\0';
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct YomitanTerm {
+    pub dictionary: String, // title of the dictionary the term is from
+    pub term: String,       // empty for kana-only terms
+    pub reading: String,
+    pub score: i64,
+    pub senses: Vec<YomitanSense>,
+}
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct YomitanSense {
+    pub tags: Vec<YomitanTag>,
+    pub glosses: Vec<String>,
+}
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct YomitanTag {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct YomitanPitch {
+    pub reading: String,
+    pub positions: Vec<u32>, // downstep after this mora, 0 for none
+}
+
+#[derive(bincode::Encode, bincode::Decode, Clone, Debug)]
+pub struct YomitanKanji {
+    pub dictionary: String,
+    pub character: String,
+    pub onyomi: Vec<String>,
+    pub kunyomi: Vec<String>,
+    pub tags: Vec<String>,
+    pub meanings: Vec<String>,
+    pub stats: Vec<(String, String)>, // e.g. strokes, grade, jlpt, freq
+}
+
+/// An imported dictionary, in the order they are looked up in.
+#[derive(Clone, Debug)]
+pub struct DictionaryInfo {
+    pub title: String,
+    pub url: Option<String>,
+}
+
+// Yomitan json
+#[derive(Deserialize)]
+struct Index {
+    title: String,
+    revision: String,
+    format: Option<u8>,
+    version: Option<u8>, // older name of format
+    url: Option<String>,
+}
+
+// [expression, reading, definition tags, rules, score, glossary, sequence, term tags]
+type TermRow = (
+    String,
+    String,
+    Option<String>,
+    String,
+    f64,
+    Vec<Value>,
+    i64,
+    String,
+);
+// [name, category, order, notes, score]
+type TagRow = (String, String, f64, String, f64);
+// [expression, mode, data]
+type TermMetaRow = (String, String, Value);
+// [character, onyomi, kunyomi, tags, meanings, stats]
+type KanjiRow = (
+    String,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, Value>,
+);
+// ---
+
+pub struct YomitanDictionary {
+    db: Db,
+    dictionaries: Vec<DictionaryInfo>,
+}
+
+impl YomitanDictionary {
+    /// Opens the database, importing every zip that isn't imported yet or changed since.
+    /// Dictionaries not in `paths` anymore are removed from it. Zips that can't be imported
+    /// are skipped, so the others can still be used.
+    pub fn load_dictionaries(db: Db, paths: &[PathBuf]) -> Result<Self, Box<dyn Error>> {
+        let mut dictionaries: Vec<DictionaryInfo> = Vec::new();
+        for path in paths {
+            let (mut archive, index, signature) = match open_zip(path) {
+                Ok(opened) => opened,
+                Err(e) => {
+                    tracing::warn!("Skipping {}, it could not be read: {e}", path.display());
+                    continue;
+                }
+            };
+            // Checked before importing, as a dictionary with the same title would replace
+            // the one in use
+            if dictionaries.iter().any(|other| other.title == index.title) {
+                tracing::warn!(
+                    "Skipping {}, a dictionary titled {} is already in use.",
+                    path.display(),
+                    index.title
+                );
+                continue;
+            }
+            if let Err(e) = Self::import_if_changed(&db, &index, &signature, &mut archive) {
+                tracing::warn!("Skipping {}, it could not be imported: {e}", path.display());
+                continue;
+            }
+            dictionaries.push(DictionaryInfo {
+                title: index.title,
+                url: index.url.filter(|url| !url.is_empty()),
+            });
+        }
+
+        for imported in db.scan_prefix("imported:").keys() {
+            let imported = imported?;
+            let title: String = String::from_utf8_lossy(&imported["imported:".len()..]).into();
+            if !dictionaries.iter().any(|info| info.title == title) {
+                tracing::info!(
+                    "Removing Yomitan dictionary {title}, it is not configured anymore."
+                );
+                Self::remove(&db, &title)?;
+            }
+        }
+        db.flush()?;
+
+        if dictionaries.is_empty() {
+            return Err(Box::from(
+                "None of the configured Yomitan dictionaries could be imported, see the log for details.",
+            ));
+        }
+
+        Ok(Self { db, dictionaries })
+    }
+
+    pub fn dictionaries(&self) -> &[DictionaryInfo] {
+        &self.dictionaries
+    }
+
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    fn import_if_changed(
+        db: &Db,
+        index: &Index,
+        signature: &str,
+        archive: &mut ZipArchive<File>,
+    ) -> Result<(), Box<dyn Error>> {
+        let imported_key: String = format!("imported:{}", index.title);
+        if db
+            .get(&imported_key)?
+            .is_none_or(|imported| imported != signature.as_bytes())
+        {
+            Self::remove(db, &index.title)?;
+            Self::import(db, &index.title, archive)?;
+            db.insert(&imported_key, signature.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    fn import(db: &Db, title: &str, archive: &mut ZipArchive<File>) -> Result<(), Box<dyn Error>> {
+        tracing::info!("Importing Yomitan dictionary {title}.");
+
+        let mut tags: HashMap<String, String> = HashMap::new();
+        for name in bank_files(archive, "tag_bank_") {
+            let rows: Vec<TagRow> = serde_json::from_str(&read_file(archive, &name)?)?;
+            for (tag, _, _, notes, _) in rows {
+                tags.insert(tag, notes);
+            }
+        }
+
+        // Several rows of the same sequence are senses of one term
+        let mut terms: HashMap<(String, String, i64), YomitanTerm> = HashMap::new();
+        for name in bank_files(archive, "term_bank_") {
+            let rows: Vec<TermRow> = serde_json::from_str(&read_file(archive, &name)?)?;
+            for (expression, reading, definition_tags, _, score, glossary, sequence, _) in rows {
+                let sense: YomitanSense = YomitanSense {
+                    tags: definition_tags
+                        .unwrap_or_default()
+                        .split_whitespace()
+                        .map(|tag| YomitanTag {
+                            name: tag.to_string(),
+                            description: tags.get(tag).cloned().unwrap_or_default(),
+                        })
+                        .collect(),
+                    glosses: glossary.iter().flat_map(glosses).collect(),
+                };
+
+                let term: &mut YomitanTerm = terms
+                    .entry((expression.clone(), reading.clone(), sequence))
+                    .or_insert_with(|| {
+                        let kana_only: bool = reading.is_empty() || reading == expression;
+                        YomitanTerm {
+                            dictionary: title.to_string(),
+                            term: if kana_only {
+                                String::new()
+                            } else {
+                                expression.clone()
+                            },
+                            reading: if kana_only { expression } else { reading },
+                            score: score as i64,
+                            senses: Vec::new(),
+                        }
+                    });
+                term.score = term.score.max(score as i64);
+                if !sense.glosses.is_empty() {
+                    term.senses.push(sense);
+                }
+            }
+        }
+
+        let mut entries: HashMap<String, Vec<YomitanTerm>> = HashMap::new();
+        for term in terms.into_values() {
+            if !term.term.is_empty() {
+                entries
+                    .entry(format!("{title}{SEPARATOR}term:{}", term.term))
+                    .or_default()
+                    .push(term.clone());
+            }
+            entries
+                .entry(format!("{title}{SEPARATOR}reading:{}", term.reading))
+                .or_default()
+                .push(term);
+        }
+        let mut batch: sled::Batch = sled::Batch::default();
+        let entry_count: usize = entries.len();
+        for (key, mut terms) in entries {
+            terms.sort_by_key(|term| std::cmp::Reverse(term.score));
+            batch.insert(
+                key.as_bytes(),
+                bincode::encode_to_vec(&terms, bincode::config::standard())?,
+            );
+        }
+
+        let mut frequency_count: usize = 0;
+        let mut pitches: HashMap<String, Vec<YomitanPitch>> = HashMap::new();
+        for name in bank_files(archive, "term_meta_bank_") {
+            let rows: Vec<TermMetaRow> = serde_json::from_str(&read_file(archive, &name)?)?;
+            for (expression, mode, data) in rows {
+                match mode.as_str() {
+                    "freq" => {
+                        let Some(frequency) = frequency_value(&data) else {
+                            continue;
+                        };
+                        batch.insert(
+                            format!("{title}{SEPARATOR}freq:{expression}").as_bytes(),
+                            &frequency.to_be_bytes(),
+                        );
+                        frequency_count += 1;
+                    }
+                    "pitch" => {
+                        if let Some(pitch) = pitch_value(&data) {
+                            pitches.entry(expression).or_default().push(pitch);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let pitch_count: usize = pitches.len();
+        for (expression, pitches) in pitches {
+            batch.insert(
+                format!("{title}{SEPARATOR}pitch:{expression}").as_bytes(),
+                bincode::encode_to_vec(&pitches, bincode::config::standard())?,
+            );
+        }
+
+        let mut kanji_count: usize = 0;
+        for name in bank_files(archive, "kanji_bank_") {
+            let rows: Vec<KanjiRow> = serde_json::from_str(&read_file(archive, &name)?)?;
+            for (character, onyomi, kunyomi, tags, meanings, stats) in rows {
+                let mut stats: Vec<(String, String)> = stats
+                    .into_iter()
+                    .map(|(name, value)| match value {
+                        Value::String(value) => (name, value),
+                        value => (name, value.to_string()),
+                    })
+                    .collect();
+                stats.sort();
+                let kanji: YomitanKanji = YomitanKanji {
+                    dictionary: title.to_string(),
+                    character: character.clone(),
+                    onyomi: onyomi.split_whitespace().map(String::from).collect(),
+                    kunyomi: kunyomi.split_whitespace().map(String::from).collect(),
+                    tags: tags.split_whitespace().map(String::from).collect(),
+                    meanings,
+                    stats,
+                };
+                batch.insert(
+                    format!("{title}{SEPARATOR}kanji:{character}").as_bytes(),
+                    bincode::encode_to_vec(&kanji, bincode::config::standard())?,
+                );
+                kanji_count += 1;
+            }
+        }
+
+        db.apply_batch(batch)?;
+        tracing::info!(
+            "Imported Yomitan dictionary {title} with {entry_count} entries, {frequency_count} frequencies, {pitch_count} pitch accents and {kanji_count} kanji."
+        );
+
+        Ok(())
+    }
+
+    fn remove(db: &Db, title: &str) -> Result<(), Box<dyn Error>> {
+        let mut batch: sled::Batch = sled::Batch::default();
+        for key in db.scan_prefix(format!("{title}{SEPARATOR}")).keys() {
+            batch.remove(key?);
+        }
+        batch.remove(format!("imported:{title}").as_bytes());
+        db.apply_batch(batch)?;
+
+        Ok(())
+    }
+
+    /// Terms of all dictionaries for a word, in the order of the dictionaries. Within one
+    /// dictionary, more frequent terms come first.
+    pub fn lookup(&self, word: &str) -> Result<Option<Vec<YomitanTerm>>, Box<dyn Error>> {
+        let mut found: Vec<YomitanTerm> = Vec::new();
+        for info in &self.dictionaries {
+            for key in ["term", "reading"] {
+                let Some(serialized_terms) = self
+                    .db
+                    .get(format!("{}{SEPARATOR}{key}:{word}", info.title))?
+                else {
+                    continue;
+                };
+                let (mut terms, _): (Vec<YomitanTerm>, usize) =
+                    bincode::decode_from_slice(&serialized_terms, bincode::config::standard())?;
+                terms.sort_by_cached_key(|term| self.frequency(term).unwrap_or(u32::MAX));
+                found.extend(terms);
+                break;
+            }
+        }
+
+        Ok((!found.is_empty()).then_some(found))
+    }
+
+    /// Pitch accents of a term with this reading from the first dictionary that has them.
+    pub fn lookup_pitch_accents(
+        &self,
+        term: &str,
+        reading: &str,
+    ) -> Result<Vec<u32>, Box<dyn Error>> {
+        for info in &self.dictionaries {
+            let Some(serialized_pitches) = self
+                .db
+                .get(format!("{}{SEPARATOR}pitch:{term}", info.title))?
+            else {
+                continue;
+            };
+            let (pitches, _): (Vec<YomitanPitch>, usize) =
+                bincode::decode_from_slice(&serialized_pitches, bincode::config::standard())?;
+            if let Some(pitch) = pitches.into_iter().find(|pitch| pitch.reading == reading) {
+                return Ok(pitch.positions);
+            }
+        }
+
+        Ok(Vec::new())
+    }
+
+    /// The kanji from the first dictionary that has it.
+    pub fn lookup_kanji(&self, character: char) -> Result<Option<YomitanKanji>, Box<dyn Error>> {
+        for info in &self.dictionaries {
+            if let Some(serialized_kanji) = self
+                .db
+                .get(format!("{}{SEPARATOR}kanji:{character}", info.title))?
+            {
+                let (kanji, _): (YomitanKanji, usize) =
+                    bincode::decode_from_slice(&serialized_kanji, bincode::config::standard())?;
+                return Ok(Some(kanji));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Frequency rank of a term in the first dictionary that has one, e.g. a frequency list
+    /// imported alongside the dictionaries.
+    fn frequency(&self, term: &YomitanTerm) -> Option<u32> {
+        self.dictionaries.iter().find_map(|info| {
+            [&term.term, &term.reading]
+                .into_iter()
+                .filter(|word| !word.is_empty())
+                .find_map(|word| {
+                    let value = self
+                        .db
+                        .get(format!("{}{SEPARATOR}freq:{word}", info.title))
+                        .ok()??;
+                    Some(u32::from_be_bytes(value.as_ref().try_into().ok()?))
+                })
+        })
+    }
+}
+
+/// Opens a dictionary zip and reads its index. The signature changes whenever the zip does.
+fn open_zip(path: &Path) -> Result<(ZipArchive<File>, Index, String), Box<dyn Error>> {
+    let file: File = File::open(path)?;
+    let metadata = file.metadata()?;
+    let modified: u64 = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut archive: ZipArchive<File> = ZipArchive::new(file)?;
+    let index: Index = serde_json::from_str(&read_file(&mut archive, "index.json")?)?;
+    if index.format.or(index.version) != Some(3) {
+        return Err(Box::from(
+            "Only dictionaries in the current Yomitan format (3) are supported.",
+        ));
+    }
+
+    let signature: String = format!("{}:{}:{modified}", index.revision, metadata.len());
+    Ok((archive, index, signature))
+}
+
+/// Pitch accents of a Yomitan zip by term, e.g. of a pitch accent dictionary used by another
+/// plugin.
+pub fn read_pitch_accents(
+    path: &Path,
+) -> Result<HashMap<String, Vec<YomitanPitch>>, Box<dyn Error>> {
+    let mut archive: ZipArchive<File> = ZipArchive::new(File::open(path)?)?;
+
+    let mut pitches: HashMap<String, Vec<YomitanPitch>> = HashMap::new();
+    for name in bank_files(&archive, "term_meta_bank_") {
+        let rows: Vec<TermMetaRow> = serde_json::from_str(&read_file(&mut archive, &name)?)?;
+        for (expression, mode, data) in rows {
+            if mode == "pitch"
+                && let Some(pitch) = pitch_value(&data)
+            {
+                pitches.entry(expression).or_default().push(pitch);
+            }
+        }
+    }
+
+    Ok(pitches)
+}
+
+/// Names of the numbered bank files starting with `prefix`, e.g. term_bank_1.json.
+fn bank_files(archive: &ZipArchive<File>, prefix: &str) -> Vec<String> {
+    let mut names: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.starts_with(prefix) && name.ends_with(".json"))
+        .map(String::from)
+        .collect();
+    names.sort_by_key(|name| {
+        name[prefix.len()..name.len() - ".json".len()]
+            .parse::<u32>()
+            .unwrap_or(u32::MAX)
+    });
+    names
+}
+
+fn read_file(archive: &mut ZipArchive<File>, name: &str) -> Result<String, Box<dyn Error>> {
+    let mut content: String = String::new();
+    archive
+        .by_name(name)
+        .map_err(|e| format!("{name}: {e}"))?
+        .read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Texts of one glossary item. Structured content is flattened to one text per line or list
+/// item, images and deinflection hints are left out.
+fn glosses(item: &Value) -> Vec<String> {
+    let text: String = match item {
+        Value::String(text) => text.to_string(),
+        Value::Object(item) => match item.get("type").and_then(Value::as_str) {
+            Some("text") => item
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            Some("structured-content") => {
+                let mut text: String = String::new();
+                if let Some(content) = item.get("content") {
+                    structured_text(content, &mut text);
+                }
+                text
+            }
+            _ => String::new(),
+        },
+        _ => String::new(),
+    };
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn structured_text(content: &Value, text: &mut String) {
+    match content {
+        Value::String(content) => text.push_str(content),
+        Value::Array(contents) => {
+            for content in contents {
+                structured_text(content, text);
+            }
+        }
+        Value::Object(element) => {
+            let tag: &str = element
+                .get("tag")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            match tag {
+                // Ruby text would end up in the middle of the word
+                "rt" | "rp" | "img" => return,
+                "br" => {
+                    text.push('\n');
+                    return;
+                }
+                _ => {}
+            }
+
+            let is_block: bool = matches!(
+                tag,
+                "div" | "li" | "ol" | "ul" | "tr" | "table" | "details" | "summary"
+            );
+            if is_block {
+                text.push('\n');
+            }
+            if let Some(content) = element.get("content") {
+                structured_text(content, text);
+            }
+            if is_block {
+                text.push('\n');
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Frequency of a freq row, which is a number, an object with a value or one of those for a
+/// specific reading.
+fn frequency_value(data: &Value) -> Option<u32> {
+    match data {
+        Value::Number(number) => number.as_f64().map(|number| number as u32),
+        Value::String(text) => text.trim().parse().ok(),
+        Value::Object(data) => data
+            .get("value")
+            .or_else(|| data.get("frequency"))
+            .and_then(frequency_value),
+        _ => None,
+    }
+}
+
+/// Pitch accents of a pitch row, `{reading, pitches: [{position}]}`. Positions given as
+/// high/low patterns are left out.
+fn pitch_value(data: &Value) -> Option<YomitanPitch> {
+    let reading: &str = data.get("reading")?.as_str()?;
+    let positions: Vec<u32> = data
+        .get("pitches")?
+        .as_array()?
+        .iter()
+        .filter_map(|pitch| pitch.get("position")?.as_u64())
+        .map(|position| position as u32)
+        .collect();
+
+    (!positions.is_empty()).then(|| YomitanPitch {
+        reading: reading.to_string(),
+        positions,
+    })
+}
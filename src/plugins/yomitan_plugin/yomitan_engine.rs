@@ -0,0 +1,81 @@
+use sled::Db;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config_file;
+use crate::plugins::yomitan_plugin::yomitan_dictionary::YomitanDictionary;
+
+// Shared by every popup like the Kihon engine. It is loaded again when the configured
+// dictionaries change, e.g. after editing the config file while watching.
+static ENGINE: Mutex<Option<Arc<YomitanEngine>>> = Mutex::new(None);
+
+pub struct YomitanEngine {
+    pub dictionary: YomitanDictionary,
+    paths: Vec<PathBuf>, // configured dictionaries the engine was loaded with
+}
+
+impl YomitanEngine {
+    /// Returns the resident engine, loading it first if this is the first call or the
+    /// configured dictionaries changed. Failed loads are not cached, so a later call tries
+    /// again.
+    pub fn get() -> Result<Arc<Self>, Box<dyn Error>> {
+        let paths: Vec<PathBuf> = configured_paths()?;
+        if paths.is_empty() {
+            return Err(Box::from(
+                "No Yomitan dictionaries configured. Add the paths of their zip files to yomitan.dictionaries in the config file.",
+            ));
+        }
+
+        let mut engine = ENGINE
+            .lock()
+            .map_err(|_| "Yomitan engine lock is poisoned.")?;
+        if let Some(engine) = engine.as_ref()
+            && engine.paths == paths
+        {
+            return Ok(Arc::clone(engine));
+        }
+
+        // The database can only be opened once per process, so a reload reuses it
+        let db: Db = match engine.as_ref() {
+            Some(engine) => engine.dictionary.db().clone(),
+            None => open_database()?,
+        };
+
+        let start: Instant = Instant::now();
+        let dictionary: YomitanDictionary = YomitanDictionary::load_dictionaries(db, &paths)?;
+        let duration: Duration = start.elapsed();
+        tracing::info!(
+            "Yomitan engine loaded in: {:.3} ms",
+            duration.as_secs_f64() * 1000.0
+        );
+
+        let loaded_engine: Arc<Self> = Arc::new(Self { dictionary, paths });
+        *engine = Some(Arc::clone(&loaded_engine));
+        Ok(loaded_engine)
+    }
+}
+
+/// Paths of the dictionaries in the config file, in the order they are looked up in.
+fn configured_paths() -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    Ok(config_file::load()?
+        .yomitan
+        .dictionaries
+        .iter()
+        .map(|path| config_file::resolve_path(path))
+        .collect())
+}
+
+fn open_database() -> Result<Db, Box<dyn Error>> {
+    let db_path: PathBuf = match dirs::data_dir() {
+        Some(path) => path.join("popup_dictionary").join("yomitan"),
+        None => {
+            return Err(Box::from(
+                "No valid data path found in environment variables.",
+            ));
+        }
+    };
+
+    sled::open(&db_path).map_err(|e| Box::from(format!("Could not open the Yomitan database: {e}")))
+}
@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::plugin::{Kanji, LookupResult, Plugin, Sense, Tag, Term, Token, unique_kanji};
+use crate::plugins::kihon_plugin::jumandic_tokenizer::tokenize;
+use crate::plugins::kihon_plugin::kihon_engine::KihonEngine;
+use crate::plugins::kihon_plugin::kihon_plugin::{conjugation_forms, fallback_words};
+use crate::plugins::yomitan_plugin::yomitan_dictionary::{YomitanKanji, YomitanTerm};
+use crate::plugins::yomitan_plugin::yomitan_engine::YomitanEngine;
+
+/// Looks words up in imported Yomitan dictionaries. The input text is tokenized like in the
+/// Kihon plugin, so the same deinflection and fallbacks apply.
+pub struct YomitanPlugin {
+    tokens: Vec<Token>,
+    kihon_engine: Arc<KihonEngine>,
+    engine: Arc<YomitanEngine>,
+}
+
+impl Plugin for YomitanPlugin {
+    fn load_plugin(sentence: &str) -> Result<Self, Box<dyn Error>> {
+        let engine: Arc<YomitanEngine> = YomitanEngine::get()?;
+        let kihon_engine: Arc<KihonEngine> = KihonEngine::get()?;
+
+        let tokens = tokenize(
+            &sentence.to_string(),
+            &kihon_engine.tokenizer,
+            &kihon_engine.dictionary,
+        )
+        .map_err(|e| format!("Could not tokenize input text: {e}"))?;
+
+        Ok(Self {
+            tokens,
+            kihon_engine,
+            engine,
+        })
+    }
+
+    fn get_tokens(&self) -> &Vec<Token> {
+        &self.tokens
+    }
+
+    fn set_tokens(&mut self, tokens: Vec<Token>) {
+        self.tokens = tokens;
+    }
+
+    fn lookup(&self, token: &Token) -> Result<LookupResult, Box<dyn Error>> {
+        let mut terms: Vec<Term> = Vec::new();
+        for word in &fallback_words(token) {
+            if let Some(found) = self.lookup_word(word)? {
+                terms = found;
+                break;
+            }
+        }
+
+        Ok(LookupResult {
+            forms: conjugation_forms(token),
+            terms,
+            kanji: self.lookup_kanji(&token.input_word)?,
+        })
+    }
+
+    fn search(&self, query: &str) -> Result<LookupResult, Box<dyn Error>> {
+        if let Some(terms) = self.lookup_word(query)? {
+            return Ok(LookupResult {
+                forms: Vec::new(),
+                terms,
+                kanji: self.lookup_kanji(query)?,
+            });
+        }
+
+        // Not a dictionary form, so let the tokenizer deinflect it if it is a single word
+        let tokens: Vec<Token> = tokenize(
+            &query.to_string(),
+            &self.kihon_engine.tokenizer,
+            &self.kihon_engine.dictionary,
+        )?;
+        match tokens.as_slice() {
+            [token] => self.lookup(token),
+            _ => Ok(LookupResult::default()),
+        }
+    }
+
+    fn open_url(&self) -> Option<String> {
+        let url: Option<String> = self
+            .engine
+            .dictionary
+            .dictionaries()
+            .iter()
+            .find_map(|info| info.url.clone());
+        match &url {
+            Some(url) => tracing::info!("Trying to open the Yomitan dictionary website {url}."),
+            None => tracing::info!("None of the Yomitan dictionaries has a website."),
+        }
+
+        url
+    }
+}
+
+impl YomitanPlugin {
+    fn lookup_word(&self, word: &str) -> Result<Option<Vec<Term>>, Box<dyn Error>> {
+        let terms: Option<Vec<YomitanTerm>> = self
+            .engine
+            .dictionary
+            .lookup(word)
+            .map_err(|e| format!("Error getting from database when looking up {word}: {e}"))?;
+
+        terms
+            .map(|terms| terms.iter().map(|term| self.build_term(term)).collect())
+            .transpose()
+    }
+
+    fn lookup_kanji(&self, word: &str) -> Result<Vec<Kanji>, Box<dyn Error>> {
+        let mut kanji: Vec<Kanji> = Vec::new();
+        for character in unique_kanji(word) {
+            if let Some(yomitan_kanji) =
+                self.engine
+                    .dictionary
+                    .lookup_kanji(character)
+                    .map_err(|e| {
+                        format!(
+                            "Error getting from database when looking up kanji {character}: {e}"
+                        )
+                    })?
+            {
+                kanji.push(Self::build_kanji(yomitan_kanji));
+            }
+        }
+
+        Ok(kanji)
+    }
+
+    /// Kanji dictionaries converted from KANJIDIC name their stats like it.
+    fn build_kanji(yomitan_kanji: YomitanKanji) -> Kanji {
+        let stat = |name: &str| -> Option<String> {
+            yomitan_kanji
+                .stats
+                .iter()
+                .find(|(stat, _)| stat == name)
+                .map(|(_, value)| value.to_string())
+        };
+
+        Kanji {
+            stroke_count: stat("strokes").and_then(|value| value.parse().ok()),
+            grade: stat("grade").and_then(|value| value.parse().ok()),
+            jlpt: stat("jlpt").and_then(|value| value.parse().ok()),
+            frequency: stat("freq").and_then(|value| value.parse().ok()),
+            character: yomitan_kanji.character,
+            meanings: yomitan_kanji.meanings,
+            onyomi: yomitan_kanji.onyomi,
+            kunyomi: yomitan_kanji.kunyomi,
+        }
+    }
+
+    fn build_term(&self, yomitan_term: &YomitanTerm) -> Result<Term, Box<dyn Error>> {
+        let written_form: &str = if yomitan_term.term.is_empty() {
+            &yomitan_term.reading
+        } else {
+            &yomitan_term.term
+        };
+        let pitch_accents: Vec<u32> = self
+            .engine
+            .dictionary
+            .lookup_pitch_accents(written_form, &yomitan_term.reading)
+            .map_err(|e| {
+                format!("Error getting pitch accents of {written_form} from database: {e}")
+            })?;
+
+        Ok(Term {
+            term: yomitan_term.term.to_string(),
+            reading: yomitan_term.reading.to_string(),
+            furigana: None,
+            pitch_accents,
+            senses: yomitan_term
+                .senses
+                .iter()
+                .enumerate()
+                .map(|(idx, sense)| Sense {
+                    tags: sense
+                        .tags
+                        .iter()
+                        .map(|tag| Tag {
+                            name: tag.name.to_string(),
+                            description: if tag.description.is_empty() {
+                                tag.name.to_string()
+                            } else {
+                                tag.description.to_string()
+                            },
+                        })
+                        .collect(),
+                    glosses: sense.glosses.to_vec(),
+                    // Tells stacked dictionaries apart
+                    info: if idx == 0 {
+                        vec![yomitan_term.dictionary.to_string()]
+                    } else {
+                        Vec::new()
+                    },
+                })
+                .collect(),
+            examples: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,142 @@
+use std::error::Error;
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xfixes::SelectionNotifyEvent;
+use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, Window, WindowClass,
+};
+use x11rb::rust_connection::RustConnection;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection {
+    Clipboard,
+    Primary,
+}
+
+pub struct SelectionChange {
+    pub owner: Window, // the window that now owns the selection
+}
+
+/// Waits for X11 selections to change owner, which happens whenever something new is copied
+/// or selected. This way content only has to be read when it actually changed.
+pub struct SelectionWatcher {
+    connection: RustConnection,
+    clipboard_atom: Atom,
+}
+
+impl SelectionWatcher {
+    pub fn new(selections: &[Selection]) -> Result<Self, Box<dyn Error>> {
+        tracing::info!("Trying to watch selections {:?} with XFixes.", selections);
+        let (connection, display_idx) = RustConnection::connect(None)?;
+
+        // The extension version has to be negotiated before any other XFixes request
+        let version = connection.xfixes_query_version(5, 0)?.reply()?;
+        tracing::debug!(
+            "Using XFixes version {}.{}.",
+            version.major_version,
+            version.minor_version
+        );
+
+        // Selection events are delivered to a window, so create an invisible one
+        let root = connection.setup().roots[display_idx].root;
+        let window = connection.generate_id()?;
+        connection.create_window(
+            0,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )?;
+
+        let clipboard_atom: Atom = connection.intern_atom(false, b"CLIPBOARD")?.reply()?.atom;
+        for selection in selections {
+            let atom: Atom = match selection {
+                Selection::Clipboard => clipboard_atom,
+                Selection::Primary => AtomEnum::PRIMARY.into(),
+            };
+            connection.xfixes_select_selection_input(
+                window,
+                atom,
+                SelectionEventMask::SET_SELECTION_OWNER,
+            )?;
+        }
+        connection.flush()?;
+
+        Ok(Self {
+            connection,
+            clipboard_atom,
+        })
+    }
+
+    /// Blocks until one of the watched selections changes.
+    pub fn wait_for_change(&self) -> Result<SelectionChange, Box<dyn Error>> {
+        loop {
+            if let Event::XfixesSelectionNotify(event) = self.connection.wait_for_event()?
+                && let Some(change) = self.to_change(&event)
+            {
+                return Ok(change);
+            }
+        }
+    }
+
+    /// Returns the latest change that happened since the last call without blocking, if any.
+    pub fn take_pending_change(&self) -> Result<Option<SelectionChange>, Box<dyn Error>> {
+        let mut latest_change: Option<SelectionChange> = None;
+        while let Some(event) = self.connection.poll_for_event()? {
+            if let Event::XfixesSelectionNotify(event) = event
+                && let Some(change) = self.to_change(&event)
+            {
+                latest_change = Some(change);
+            }
+        }
+
+        Ok(latest_change)
+    }
+
+    fn to_change(&self, event: &SelectionNotifyEvent) -> Option<SelectionChange> {
+        if event.selection != self.clipboard_atom
+            && event.selection != Atom::from(AtomEnum::PRIMARY)
+        {
+            return None;
+        }
+
+        Some(SelectionChange { owner: event.owner })
+    }
+
+    /// Returns the WM_CLASS of the window or its closest parent that has one. Selection owners
+    /// are often hidden helper windows, so the class is searched for up the window tree.
+    pub fn window_class(&self, window: Window) -> Option<String> {
+        let mut window: Window = window;
+        while window != x11rb::NONE {
+            let property = self
+                .connection
+                .get_property(false, window, AtomEnum::WM_CLASS, AtomEnum::STRING, 0, 1024)
+                .ok()?
+                .reply()
+                .ok()?;
+            if !property.value.is_empty() {
+                // WM_CLASS holds the instance and class name, separated by null bytes
+                return Some(
+                    String::from_utf8_lossy(&property.value)
+                        .trim_end_matches('\0')
+                        .replace('\0', " "),
+                );
+            }
+
+            let tree = self.connection.query_tree(window).ok()?.reply().ok()?;
+            if tree.parent == tree.root {
+                break;
+            }
+            window = tree.parent;
+        }
+
+        None
+    }
+}
@@ -0,0 +1,339 @@
+use eframe::egui;
+use egui::RichText;
+use std::error::Error;
+use std::str::FromStr;
+
+use crate::app::Config;
+use crate::config_file::{self, ConfigFile, OCR_ENGINES};
+use crate::plugin::{PluginDescriptor, PluginRegistry};
+use crate::theme::{Theme, ThemePreset};
+use crate::watch_filter::{WatchFilter, WatchFilters};
+
+pub enum SettingsAction {
+    Save,
+    Close,
+}
+
+/// Values being edited in the settings panel. Numbers and lists are edited as text and only
+/// checked when saving.
+pub struct Settings {
+    plugin_index: usize,
+    ocr_model: usize,
+    font: String,
+    text_scale: f32,
+    theme_preset: ThemePreset,
+    wrapped: bool,
+    furigana: bool,
+    open_at_cursor: bool,
+    remember_corrections: bool,
+    min_length: String,
+    max_length: String,
+    debounce_ms: String,
+    min_image_size: String,
+    ignore_patterns: String, // one per line
+    allowed_sources: String, // one per line
+    error: Option<String>,
+    // What the panel was opened with, so only changed settings are written to the file and
+    // options given on the command line stay one-off
+    opened_config: Config,
+    opened_plugin_index: usize,
+    opened_ocr_model: usize,
+}
+
+impl Settings {
+    pub fn new(config: &Config, plugin_index: usize, ocr_model: usize) -> Self {
+        let filters: &WatchFilters = &config.filters;
+        Self {
+            plugin_index,
+            ocr_model,
+            font: config.font.to_owned(),
+            text_scale: config.text_scale,
+            theme_preset: config.theme.preset,
+            wrapped: config.wrapped,
+            furigana: config.furigana,
+            open_at_cursor: config.open_at_cursor,
+            remember_corrections: config.remember_corrections,
+            min_length: optional_to_string(filters.min_length),
+            max_length: optional_to_string(filters.max_length),
+            debounce_ms: optional_to_string(filters.debounce_ms),
+            min_image_size: optional_to_string(filters.min_image_size),
+            ignore_patterns: filters.ignore_patterns.join("\n"),
+            allowed_sources: filters.allowed_sources.join("\n"),
+            error: None,
+            opened_config: config.clone(),
+            opened_plugin_index: plugin_index,
+            opened_ocr_model: ocr_model,
+        }
+    }
+
+    pub fn plugin_index(&self) -> usize {
+        self.plugin_index
+    }
+
+    pub fn ocr_model(&self) -> usize {
+        self.ocr_model
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    /// Returns a copy of `config` with the edited settings applied. Unchanged options given on
+    /// the command line stay in effect for this run, but `save` leaves them out of the file.
+    pub fn to_config(&self, config: &Config) -> Result<Config, Box<dyn Error>> {
+        if self.font.trim().is_empty() {
+            return Err(Box::from("Font must not be empty."));
+        }
+
+        let filters = WatchFilters {
+            min_length: parse_optional("Min length", &self.min_length)?,
+            max_length: parse_optional("Max length", &self.max_length)?,
+            ignore_patterns: lines(&self.ignore_patterns),
+            debounce_ms: parse_optional("Debounce", &self.debounce_ms)?,
+            min_image_size: parse_optional("Min image size", &self.min_image_size)?,
+            allowed_sources: lines(&self.allowed_sources),
+        };
+        if let (Some(min_length), Some(max_length)) = (filters.min_length, filters.max_length)
+            && min_length > max_length
+        {
+            return Err(Box::from("Min length must not be greater than max length."));
+        }
+        WatchFilter::new(filters.clone())?;
+
+        let mut config: Config = config.clone();
+        config.initial_plugin = PluginRegistry::global()
+            .all()
+            .get(self.plugin_index)
+            .map(|plugin| plugin.name.to_string());
+        config.font = self.font.trim().to_string();
+        config.text_scale = self.text_scale;
+        config.theme.preset = self.theme_preset;
+        config.wrapped = self.wrapped;
+        config.furigana = self.furigana;
+        config.open_at_cursor = self.open_at_cursor;
+        config.remember_corrections = self.remember_corrections;
+        config.filters = filters;
+
+        Ok(config)
+    }
+
+    /// Writes the settings changed in the panel to the config file, `config` being the result
+    /// of `to_config`. Anything else is kept as it is in the file.
+    pub fn save(&self, config: &Config) -> Result<(), Box<dyn Error>> {
+        let opened: &Config = &self.opened_config;
+        let mut file_config: ConfigFile = config_file::load()?;
+        if self.plugin_index != self.opened_plugin_index {
+            file_config.plugins.initial = config.initial_plugin.to_owned();
+        }
+        if self.ocr_model != self.opened_ocr_model {
+            file_config.ocr.engine = OCR_ENGINES
+                .get(self.ocr_model)
+                .map(|engine| engine.to_string());
+        }
+        if config.font != opened.font {
+            file_config.window.font = Some(config.font.to_owned());
+        }
+        if config.text_scale != opened.text_scale {
+            file_config.window.text_scale = Some(config.text_scale);
+        }
+        // Color and size overrides can only be set in the file, so they are kept
+        if config.theme.preset != opened.theme.preset {
+            file_config.theme.preset = config.theme.preset;
+        }
+        if config.wrapped != opened.wrapped {
+            file_config.window.full_text = config.wrapped;
+        }
+        if config.furigana != opened.furigana {
+            file_config.window.furigana = config.furigana;
+        }
+        if config.open_at_cursor != opened.open_at_cursor {
+            file_config.window.at_mouse = config.open_at_cursor;
+        }
+        if config.remember_corrections != opened.remember_corrections {
+            file_config.tokens.remember_corrections = config.remember_corrections;
+        }
+
+        let (filters, opened_filters) = (&config.filters, &opened.filters);
+        if filters.min_length != opened_filters.min_length {
+            file_config.watch.min_length = filters.min_length;
+        }
+        if filters.max_length != opened_filters.max_length {
+            file_config.watch.max_length = filters.max_length;
+        }
+        if filters.ignore_patterns != opened_filters.ignore_patterns {
+            file_config.watch.ignore = filters.ignore_patterns.clone();
+        }
+        if filters.debounce_ms != opened_filters.debounce_ms {
+            file_config.watch.debounce_ms = filters.debounce_ms;
+        }
+        if filters.min_image_size != opened_filters.min_image_size {
+            file_config.watch.min_image_size = filters.min_image_size;
+        }
+        if filters.allowed_sources != opened_filters.allowed_sources {
+            file_config.watch.allow_sources = filters.allowed_sources.clone();
+        }
+
+        file_config.validate()?;
+        config_file::save(&file_config)
+    }
+
+    /// Draws the settings and returns what the user chose to do with them.
+    pub fn display(&mut self, ui: &mut egui::Ui, theme: &Theme) -> Option<SettingsAction> {
+        let mut action: Option<SettingsAction> = None;
+        ui.style_mut().override_text_style = Some(egui::TextStyle::Small);
+
+        let plugins: &'static [PluginDescriptor] = PluginRegistry::global().all();
+        egui::Grid::new("settings_general")
+            .num_columns(2)
+            .spacing(egui::vec2(theme.spacing_size, theme.spacing_size * 0.5))
+            .show(ui, |ui| {
+                ui.label("Default plugin");
+                egui::ComboBox::from_id_salt("settings_plugin")
+                    .selected_text(plugins.get(self.plugin_index).map_or("", |p| p.name))
+                    .show_ui(ui, |ui| {
+                        for (idx, plugin) in plugins.iter().enumerate() {
+                            ui.selectable_value(&mut self.plugin_index, idx, plugin.name)
+                                .on_hover_text(
+                                    RichText::new(plugin.description).size(theme.tiny_text_size),
+                                );
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("OCR engine");
+                egui::ComboBox::from_id_salt("settings_ocr_engine")
+                    .selected_text(*OCR_ENGINES.get(self.ocr_model).unwrap_or(&""))
+                    .show_ui(ui, |ui| {
+                        for (idx, engine) in OCR_ENGINES.iter().enumerate() {
+                            ui.selectable_value(&mut self.ocr_model, idx, *engine);
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Font");
+                ui.text_edit_singleline(&mut self.font);
+                ui.end_row();
+
+                ui.label("Text size");
+                ui.add(
+                    egui::Slider::new(&mut self.text_scale, config_file::TEXT_SCALES)
+                        .step_by(0.1)
+                        .custom_formatter(|scale, _| format!("{:.0}%", scale * 100.0))
+                        .custom_parser(|text| {
+                            text.trim_end_matches('%')
+                                .trim()
+                                .parse::<f64>()
+                                .ok()
+                                .map(|percent| percent / 100.0)
+                        }),
+                );
+                ui.end_row();
+
+                ui.label("Theme");
+                egui::ComboBox::from_id_salt("settings_theme")
+                    .selected_text(self.theme_preset.name())
+                    .show_ui(ui, |ui| {
+                        for preset in ThemePreset::ALL {
+                            ui.selectable_value(&mut self.theme_preset, preset, preset.name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("Full text");
+                ui.checkbox(&mut self.wrapped, "");
+                ui.end_row();
+
+                ui.label("Furigana");
+                ui.checkbox(&mut self.furigana, "");
+                ui.end_row();
+
+                ui.label("Open at mouse");
+                ui.checkbox(&mut self.open_at_cursor, "");
+                ui.end_row();
+
+                ui.label("Remember word corrections");
+                ui.checkbox(&mut self.remember_corrections, "");
+                ui.end_row();
+            });
+
+        ui.add_space(theme.spacing_size);
+        ui.label(RichText::new("Watch filters").strong());
+        egui::Grid::new("settings_watch")
+            .num_columns(2)
+            .spacing(egui::vec2(theme.spacing_size, theme.spacing_size * 0.5))
+            .show(ui, |ui| {
+                for (label, value) in [
+                    ("Min length", &mut self.min_length),
+                    ("Max length", &mut self.max_length),
+                    ("Debounce (ms)", &mut self.debounce_ms),
+                    ("Min image size (px)", &mut self.min_image_size),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::TextEdit::singleline(value).hint_text("off"));
+                    ui.end_row();
+                }
+
+                for (label, value) in [
+                    ("Ignore patterns", &mut self.ignore_patterns),
+                    ("Allowed sources", &mut self.allowed_sources),
+                ] {
+                    ui.label(label);
+                    ui.add(
+                        egui::TextEdit::multiline(value)
+                            .desired_rows(2)
+                            .hint_text("one per line"),
+                    );
+                    ui.end_row();
+                }
+            });
+        ui.label(
+            RichText::new("Watch filters apply to watch modes only.")
+                .size(theme.tiny_text_size)
+                .color(theme.secondary_text_color),
+        );
+
+        if let Some(error) = &self.error {
+            ui.add_space(theme.spacing_size * 0.5);
+            ui.label(
+                RichText::new(error)
+                    .size(theme.tiny_text_size)
+                    .color(theme.error_text_color),
+            );
+        }
+
+        ui.add_space(theme.spacing_size);
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                action = Some(SettingsAction::Save);
+            }
+            if ui.button("Cancel").clicked() {
+                action = Some(SettingsAction::Close);
+            }
+        });
+
+        action
+    }
+}
+
+fn optional_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+fn parse_optional<T: FromStr>(name: &str, text: &str) -> Result<Option<T>, Box<dyn Error>> {
+    let text: &str = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    text.parse::<T>()
+        .map(Some)
+        .map_err(|_| Box::from(format!("{name} must be a positive whole number.")))
+}
+
+fn lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,230 @@
+use eframe::egui;
+use egui::Color32;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 3] = [Self::Dark, Self::Light, Self::HighContrast];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Dark => "Dark",
+            Self::Light => "Light",
+            Self::HighContrast => "High contrast",
+        }
+    }
+}
+
+/// The theme as configured: a preset and the parts of it the user changed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub preset: ThemePreset,
+    pub colors: ColorOverrides,
+    pub sizes: SizeOverrides,
+}
+
+// Colors are hex strings like "#1e1e1e"
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorOverrides {
+    pub primary_background: Option<String>,
+    pub secondary_background: Option<String>,
+    pub primary_text: Option<String>,
+    pub secondary_text: Option<String>,
+    pub light_text: Option<String>,
+    pub error_text: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SizeOverrides {
+    pub big_text: Option<f32>,
+    pub primary_text: Option<f32>,
+    pub small_text: Option<f32>,
+    pub tiny_text: Option<f32>,
+    pub spacing: Option<f32>,
+    pub corner_radius: Option<u8>,
+}
+
+/// Colors and sizes everything in the window is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub dark_mode: bool,
+    pub outline_width: f32, // outlines buttons and fields, for the high contrast preset
+    pub primary_background_color: Color32,
+    pub secondary_background_color: Color32,
+    pub primary_text_color: Color32,
+    pub secondary_text_color: Color32,
+    pub light_text_color: Color32,
+    pub error_text_color: Color32,
+    pub big_text_size: f32,
+    pub primary_text_size: f32,
+    pub small_text_size: f32,
+    pub tiny_text_size: f32,
+    pub spacing_size: f32,
+    pub corner_radius: u8,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemePreset::Dark)
+    }
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Self {
+        let dark = Self {
+            dark_mode: true,
+            outline_width: 0.0,
+            primary_background_color: Color32::from_rgb(30, 30, 30),
+            secondary_background_color: Color32::from_rgb(50, 50, 50),
+            primary_text_color: Color32::WHITE,
+            secondary_text_color: Color32::GRAY,
+            light_text_color: Color32::LIGHT_GRAY,
+            error_text_color: Color32::from_rgb(230, 90, 90),
+            big_text_size: 24.0,
+            primary_text_size: 20.0,
+            small_text_size: 18.0,
+            tiny_text_size: 14.0,
+            spacing_size: 10.0,
+            corner_radius: 4,
+        };
+
+        match preset {
+            ThemePreset::Dark => dark,
+            ThemePreset::Light => Self {
+                dark_mode: false,
+                primary_background_color: Color32::from_rgb(248, 248, 248),
+                secondary_background_color: Color32::from_rgb(222, 222, 222),
+                primary_text_color: Color32::from_rgb(20, 20, 20),
+                secondary_text_color: Color32::from_rgb(110, 110, 110),
+                light_text_color: Color32::from_rgb(70, 70, 70),
+                error_text_color: Color32::from_rgb(180, 30, 30),
+                ..dark
+            },
+            ThemePreset::HighContrast => Self {
+                outline_width: 1.0,
+                primary_background_color: Color32::BLACK,
+                secondary_background_color: Color32::from_rgb(70, 70, 70),
+                primary_text_color: Color32::WHITE,
+                secondary_text_color: Color32::from_rgb(220, 220, 220),
+                light_text_color: Color32::WHITE,
+                error_text_color: Color32::from_rgb(255, 120, 120),
+                ..dark
+            },
+        }
+    }
+
+    /// Faint lines between sections, derived from the secondary text color so they show up on
+    /// light and dark backgrounds alike. Higher `alpha` gives a more visible line.
+    pub fn separator_color(&self, alpha: u8) -> Color32 {
+        let color: Color32 = self.secondary_text_color;
+        Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha)
+    }
+
+    /// Base visuals for the theme, which `MyApp::set_theme` adjusts further.
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark_mode {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        visuals.override_text_color = Some(self.primary_text_color);
+        visuals.window_fill = self.primary_background_color;
+        if self.outline_width > 0.0 {
+            let outline = egui::Stroke::new(self.outline_width, self.primary_text_color);
+            visuals.widgets.inactive.bg_stroke = outline;
+            visuals.widgets.hovered.bg_stroke = outline;
+            visuals.widgets.active.bg_stroke = outline;
+            visuals.selection.stroke = outline;
+        }
+
+        visuals
+    }
+}
+
+impl ThemeConfig {
+    /// The preset with the overrides applied.
+    pub fn resolve(&self) -> Result<Theme, Box<dyn Error>> {
+        let mut theme: Theme = Theme::preset(self.preset);
+
+        let colors: &ColorOverrides = &self.colors;
+        for (name, value, color) in [
+            (
+                "primary_background",
+                &colors.primary_background,
+                &mut theme.primary_background_color,
+            ),
+            (
+                "secondary_background",
+                &colors.secondary_background,
+                &mut theme.secondary_background_color,
+            ),
+            (
+                "primary_text",
+                &colors.primary_text,
+                &mut theme.primary_text_color,
+            ),
+            (
+                "secondary_text",
+                &colors.secondary_text,
+                &mut theme.secondary_text_color,
+            ),
+            (
+                "light_text",
+                &colors.light_text,
+                &mut theme.light_text_color,
+            ),
+            (
+                "error_text",
+                &colors.error_text,
+                &mut theme.error_text_color,
+            ),
+        ] {
+            if let Some(value) = value {
+                *color = Color32::from_hex(value).map_err(|_| {
+                    format!(
+                        "Invalid color {value} for theme.colors.{name}, expected e.g. \"#1e1e1e\"."
+                    )
+                })?;
+            }
+        }
+
+        let sizes: &SizeOverrides = &self.sizes;
+        for (name, value, size) in [
+            ("big_text", sizes.big_text, &mut theme.big_text_size),
+            (
+                "primary_text",
+                sizes.primary_text,
+                &mut theme.primary_text_size,
+            ),
+            ("small_text", sizes.small_text, &mut theme.small_text_size),
+            ("tiny_text", sizes.tiny_text, &mut theme.tiny_text_size),
+            ("spacing", sizes.spacing, &mut theme.spacing_size),
+        ] {
+            if let Some(value) = value {
+                if !(1.0..=200.0).contains(&value) {
+                    return Err(Box::from(format!(
+                        "theme.sizes.{name} must be between 1 and 200, got {value}."
+                    )));
+                }
+                *size = value;
+            }
+        }
+        if let Some(corner_radius) = sizes.corner_radius {
+            theme.corner_radius = corner_radius;
+        }
+
+        Ok(theme)
+    }
+}
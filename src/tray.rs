@@ -3,7 +3,13 @@ use std::sync::{
     atomic::{AtomicBool, AtomicUsize, Ordering},
 };
 
-pub fn spawn_tray_icon(paused: Arc<AtomicBool>, ocr_model: Arc<AtomicUsize>) {
+use popup_dictionary::plugin::PluginRegistry;
+
+pub fn spawn_tray_icon(
+    paused: Arc<AtomicBool>,
+    ocr_model: Arc<AtomicUsize>,
+    active_plugin: Arc<AtomicUsize>,
+) {
     tracing::info!("Spawning tray icon.");
 
     #[cfg(target_os = "linux")]
@@ -16,7 +22,11 @@ pub fn spawn_tray_icon(paused: Arc<AtomicBool>, ocr_model: Arc<AtomicUsize>) {
                 .unwrap();
 
             rt.block_on(async {
-                let tray = MyTray { paused, ocr_model };
+                let tray = MyTray {
+                    paused,
+                    ocr_model,
+                    active_plugin,
+                };
                 let _handle = tray.spawn().await.unwrap();
 
                 std::future::pending::<()>().await;
@@ -28,7 +38,7 @@ pub fn spawn_tray_icon(paused: Arc<AtomicBool>, ocr_model: Arc<AtomicUsize>) {
         std::thread::spawn(move || {
             use tray_icon::{
                 Icon, TrayIconBuilder,
-                menu::{Menu, MenuEvent, MenuItem},
+                menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, Submenu},
             };
             use windows_sys::Win32::UI::WindowsAndMessaging::{
                 DispatchMessageW, GetMessageW, MSG, TranslateMessage,
@@ -53,6 +63,20 @@ pub fn spawn_tray_icon(paused: Arc<AtomicBool>, ocr_model: Arc<AtomicUsize>) {
             let ocr_item = MenuItem::new(ocr_label, true, None);
             let pause_item = MenuItem::new("Pause", true, None);
             let quit_item = MenuItem::new("Exit", true, None);
+            let plugin_menu = Submenu::new("Plugin", true);
+            let active_plugin_idx = active_plugin.load(Ordering::Relaxed);
+            let plugin_items: Vec<CheckMenuItem> = PluginRegistry::global()
+                .all()
+                .iter()
+                .enumerate()
+                .map(|(idx, plugin)| {
+                    CheckMenuItem::new(plugin.name, true, idx == active_plugin_idx, None)
+                })
+                .collect();
+            for plugin_item in &plugin_items {
+                plugin_menu.append(plugin_item).unwrap();
+            }
+            tray_menu.append(&plugin_menu).unwrap();
             tray_menu.append(&ocr_item).unwrap();
             tray_menu.append(&pause_item).unwrap();
             tray_menu.append(&quit_item).unwrap();
@@ -79,6 +103,13 @@ pub fn spawn_tray_icon(paused: Arc<AtomicBool>, ocr_model: Arc<AtomicUsize>) {
                                 tracing::info!("Pausing watcher.");
                                 pause_item.set_text("Resume");
                             }
+                        } else if let Some(idx) =
+                            plugin_items.iter().position(|item| event.id == item.id())
+                        {
+                            active_plugin.store(idx, Ordering::Relaxed);
+                            for (item_idx, plugin_item) in plugin_items.iter().enumerate() {
+                                plugin_item.set_checked(item_idx == idx);
+                            }
                         } else if event.id == ocr_item.id() {
                             let previous_model = ocr_model.fetch_xor(1, Ordering::Relaxed);
                             if previous_model == 0 {
@@ -102,6 +133,7 @@ pub fn spawn_tray_icon(paused: Arc<AtomicBool>, ocr_model: Arc<AtomicUsize>) {
 struct MyTray {
     paused: Arc<AtomicBool>,
     ocr_model: Arc<AtomicUsize>,
+    active_plugin: Arc<AtomicUsize>,
 }
 
 #[cfg(target_os = "linux")]
@@ -143,6 +175,31 @@ impl ksni::Tray for MyTray {
         let is_paused = self.paused.load(Ordering::Relaxed);
         let pause_label = if is_paused { "Resume" } else { "Pause" };
         vec![
+            SubMenu {
+                label: "Plugin".into(),
+                submenu: vec![
+                    RadioGroup {
+                        selected: self.active_plugin.load(Ordering::Relaxed),
+                        select: Box::new(|this: &mut Self, idx| {
+                            if let Some(plugin) = PluginRegistry::global().all().get(idx) {
+                                tracing::info!("Switching watcher plugin to {}.", plugin.name);
+                            }
+                            this.active_plugin.store(idx, Ordering::Relaxed);
+                        }),
+                        options: PluginRegistry::global()
+                            .all()
+                            .iter()
+                            .map(|plugin| RadioItem {
+                                label: plugin.name.into(),
+                                ..Default::default()
+                            })
+                            .collect(),
+                    }
+                    .into(),
+                ],
+                ..Default::default()
+            }
+            .into(),
             StandardItem {
                 label: ocr_label.into(),
                 activate: Box::new(|this: &mut Self| {
@@ -0,0 +1,195 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::Duration;
+
+/// Limits on what the watch modes react to, so copies during normal work don't open popups.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct WatchFilters {
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub ignore_patterns: Vec<String>,
+    pub debounce_ms: Option<u64>,
+    pub min_image_size: Option<u32>,
+    pub allowed_sources: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct WatchFilter {
+    filters: WatchFilters,
+    ignore_patterns: Vec<Regex>,
+}
+
+impl WatchFilter {
+    pub fn new(filters: WatchFilters) -> Result<Self, Box<dyn Error>> {
+        let ignore_patterns: Vec<Regex> = filters
+            .ignore_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| format!("Invalid ignore pattern {pattern}: {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            filters,
+            ignore_patterns,
+        })
+    }
+
+    /// Returns why the text should be ignored, if it should be.
+    pub fn check_text(&self, text: &str) -> Result<(), String> {
+        // Count the same way the input text is cleaned up before the lookup
+        let length: usize = text.chars().filter(|c| !c.is_whitespace()).count();
+        if let Some(min_length) = self.filters.min_length
+            && length < min_length
+        {
+            return Err(format!("shorter than {min_length} characters"));
+        }
+        if let Some(max_length) = self.filters.max_length
+            && length > max_length
+        {
+            return Err(format!("longer than {max_length} characters"));
+        }
+        if let Some(pattern) = self
+            .ignore_patterns
+            .iter()
+            .find(|pattern| pattern.is_match(text))
+        {
+            return Err(format!("matches ignore pattern {}", pattern.as_str()));
+        }
+
+        Ok(())
+    }
+
+    pub fn allows_image(&self, width: u32, height: u32) -> bool {
+        self.filters
+            .min_image_size
+            .is_none_or(|min_size| width >= min_size && height >= min_size)
+    }
+
+    /// Whether content from a window with the given class should be looked up. Content from
+    /// unknown sources is only ignored if an allowlist is set.
+    pub fn allows_source(&self, source: Option<&str>) -> bool {
+        if self.filters.allowed_sources.is_empty() {
+            return true;
+        }
+        let Some(source) = source else {
+            return false;
+        };
+
+        let source: String = source.to_lowercase();
+        self.filters
+            .allowed_sources
+            .iter()
+            .any(|allowed| source.contains(&allowed.to_lowercase()))
+    }
+
+    pub fn has_source_allowlist(&self) -> bool {
+        !self.filters.allowed_sources.is_empty()
+    }
+
+    pub fn debounce(&self, default: Duration) -> Duration {
+        self.filters
+            .debounce_ms
+            .map_or(default, Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(filters: WatchFilters) -> WatchFilter {
+        WatchFilter::new(filters).unwrap()
+    }
+
+    #[test]
+    fn no_filters() {
+        let filter: WatchFilter = WatchFilter::default();
+
+        assert_eq!(filter.check_text("猫"), Ok(()));
+        assert!(filter.allows_source(None));
+        assert!(filter.allows_image(1, 1));
+        assert_eq!(
+            filter.debounce(Duration::from_millis(300)),
+            Duration::from_millis(300)
+        );
+    }
+
+    #[test]
+    fn length_bounds() {
+        let filter: WatchFilter = filter(WatchFilters {
+            min_length: Some(2),
+            max_length: Some(4),
+            ..Default::default()
+        });
+
+        assert!(filter.check_text("猫").is_err());
+        assert_eq!(filter.check_text("猫だ"), Ok(()));
+        assert_eq!(filter.check_text("猫です。"), Ok(()));
+        assert!(filter.check_text("猫ですね。").is_err());
+    }
+
+    #[test]
+    fn length_skips_whitespace() {
+        let filter: WatchFilter = filter(WatchFilters {
+            min_length: Some(2),
+            max_length: Some(2),
+            ..Default::default()
+        });
+
+        assert!(filter.check_text(" 猫\n\t").is_err());
+        assert_eq!(filter.check_text(" 猫 だ\n"), Ok(()));
+    }
+
+    #[test]
+    fn ignore_patterns() {
+        let filter: WatchFilter = filter(WatchFilters {
+            ignore_patterns: vec![r"^https?://".to_string(), r"\d{3}".to_string()],
+            ..Default::default()
+        });
+
+        assert_eq!(filter.check_text("猫です"), Ok(()));
+        assert!(filter.check_text("https://example.com/猫").is_err());
+        assert!(filter.check_text("猫が123匹").is_err());
+        assert_eq!(filter.check_text("猫が12匹"), Ok(()));
+    }
+
+    #[test]
+    fn invalid_ignore_pattern() {
+        assert!(
+            WatchFilter::new(WatchFilters {
+                ignore_patterns: vec!["(".to_string()],
+                ..Default::default()
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn source_allowlist() {
+        let filter: WatchFilter = filter(WatchFilters {
+            allowed_sources: vec!["Firefox".to_string(), "mpv".to_string()],
+            ..Default::default()
+        });
+
+        assert!(filter.has_source_allowlist());
+        assert!(filter.allows_source(Some("firefox")));
+        assert!(filter.allows_source(Some("org.mozilla.FIREFOX")));
+        assert!(filter.allows_source(Some("MPV")));
+        assert!(!filter.allows_source(Some("kitty")));
+        assert!(!filter.allows_source(None));
+    }
+
+    #[test]
+    fn image_size() {
+        let filter: WatchFilter = filter(WatchFilters {
+            min_image_size: Some(50),
+            ..Default::default()
+        });
+
+        assert!(filter.allows_image(50, 80));
+        assert!(!filter.allows_image(49, 80));
+        assert!(!filter.allows_image(80, 49));
+    }
+}